@@ -0,0 +1,73 @@
+use crate::{
+    ServerMessage,
+    protocol::{Ability, DASH_SPEED},
+    server::ServerNetwork,
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_rapier2d::prelude::{ExternalImpulse, ReadMassProperties};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, tick_cooldowns);
+}
+
+/// Triggers an ability for a player if it is off cooldown. Either way the owner is told how
+/// long is left, so a client that predicted a dash the server refused gets corrected.
+pub fn use_ability(
+    network: &mut ServerNetwork,
+    client_id: u64,
+    cooldowns: &mut AbilityCooldowns,
+    impulse: &mut ExternalImpulse,
+    mass: &ReadMassProperties,
+    ability: Ability,
+    direction: Vec2,
+) {
+    let direction = direction.normalize_or_zero();
+
+    if cooldowns.remaining_secs(ability) == 0.0 && direction != Vec2::ZERO {
+        match ability {
+            Ability::Dash => {
+                // Scaled by mass so the dash adds the same speed whatever the body weighs
+                impulse.impulse += direction * DASH_SPEED * mass.get().mass;
+            }
+        }
+        cooldowns.start(ability);
+    }
+
+    network.send(
+        client_id,
+        &ServerMessage::AbilityCooldown {
+            ability,
+            remaining_secs: cooldowns.remaining_secs(ability),
+        },
+    );
+}
+
+// === Cooldowns ===
+fn tick_cooldowns(time: Res<Time>, mut players: Query<&mut AbilityCooldowns>) {
+    for mut cooldowns in players.iter_mut() {
+        cooldowns
+            .0
+            .retain(|_, timer| !timer.tick(time.delta()).finished());
+    }
+}
+
+// === Components and Resources ===
+/// Abilities a player has used recently, with the time until each is ready again
+#[derive(Component, Default)]
+#[require(ExternalImpulse, ReadMassProperties)]
+pub struct AbilityCooldowns(HashMap<Ability, Timer>);
+
+impl AbilityCooldowns {
+    pub fn remaining_secs(&self, ability: Ability) -> f32 {
+        self.0
+            .get(&ability)
+            .map_or(0.0, |timer| timer.remaining_secs())
+    }
+
+    fn start(&mut self, ability: Ability) {
+        self.0.insert(
+            ability,
+            Timer::from_seconds(ability.cooldown_secs(), TimerMode::Once),
+        );
+    }
+}
@@ -0,0 +1,991 @@
+use crate::{
+    BoxCollectable, ServerMessage,
+    anticheat::{CheatWatch, SUSPICION_THRESHOLD},
+    arg_value,
+    flood::ConnectThrottle,
+    map::MapDefinition,
+    protocol::{ChatScope, ConnectData, MAX_NAME_LEN, PlayerProfile},
+    rounds::MatchRules,
+    server::{
+        CollectibleEntityMap, CollectibleId, DisconnectedPlayer, GameRng, MapSolid, NetworkIds,
+        Player, PlayerEntityMap, Score, ServerNetwork, SessionMap, SessionToken, TickRate,
+        drop_player, prepare_map, spawn_solids,
+    },
+    spawner::{CollectibleSpawner, respawn_collectibles},
+    spectators::Spectators,
+};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier2d::prelude::Velocity;
+use bevy_renet2::netcode::NetcodeServerTransport;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    net::{IpAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+const DEFAULT_BAN_FILE: &str = "bans.txt";
+
+/// Seconds between telling a client it's been kicked and disconnecting it, so the reason
+/// arrives first
+const KICK_DELAY_SECS: f32 = 0.25;
+
+/// How long a remote admin waits for a command to run before giving up on it
+const RCON_REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Slows down password guessing. Each failure from the same address doubles the wait before
+/// it may try again, up to [`RCON_MAX_LOCKOUT`].
+const RCON_LOGIN_FAILURE_DELAY: Duration = Duration::from_secs(1);
+
+const RCON_MAX_LOCKOUT: Duration = Duration::from_secs(300);
+
+/// Remote admin sessions open at once; more are turned away
+const MAX_RCON_CONNECTIONS: usize = 4;
+
+/// How long a new remote admin connection has to send the password
+const RCON_LOGIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Gap between players when they are moved to a new map's spawn
+const SPAWN_SPACING: f32 = 50.0;
+
+const HELP: &str = "\
+players                      list players and spectators with their ping and address
+kick <id> [reason]           disconnect a player
+ban <id|ip>                  kick and refuse a player's address, or an address
+unban <ip>                   lift a ban
+bans                         list banned addresses
+suspects                     list clients the anti-cheat finds suspicious
+say <message>                announce something to everyone
+tickrate <hz>                change how many frames a second the server runs
+respawn                      replace every collectible
+map <path>                   switch to another map
+rules [<name> <value>]       show the match rules, or change one";
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Kicks>()
+        .add_event::<AdminCommand>()
+        .add_systems(Startup, start_admin_inputs)
+        .add_systems(
+            Update,
+            (
+                read_admin_input,
+                (player_commands, server_commands, world_commands),
+            )
+                .chain(),
+        )
+        .add_systems(Update, disconnect_kicked);
+}
+
+// === Admin Input ===
+/// Reads commands from stdin and, if configured, from remote admins over TCP. Both run on
+/// their own threads and hand lines over to [`read_admin_input`].
+fn start_admin_inputs(mut commands: Commands, config: Res<AdminConfig>) {
+    let (requests, inbox) = mpsc::channel();
+    commands.insert_resource(AdminInbox(Mutex::new(inbox)));
+
+    if config.console {
+        let requests = requests.clone();
+        thread::spawn(move || {
+            for line in io::stdin().lines().map_while(Result::ok) {
+                let request = AdminRequest {
+                    line,
+                    reply: Reply::Console,
+                };
+                if requests.send(request).is_err() {
+                    break;
+                }
+            }
+        });
+        info!("Admin console ready, type help for commands");
+    }
+
+    let Some(addr) = config.rcon.clone() else {
+        return;
+    };
+    let Some(password) = config.rcon_password.clone().filter(|p| !p.is_empty()) else {
+        error!("Remote admin needs a password, set one with --rcon-password");
+        return;
+    };
+    let listener = match TcpListener::bind(&addr) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Failed to listen for remote admins on {addr}: {e}");
+            return;
+        }
+    };
+
+    info!("Listening for remote admins on {addr}");
+    thread::spawn(move || {
+        let failures = Arc::new(LoginFailures::default());
+        // Only this thread adds connections, so checking then adding can't race
+        let active = Arc::new(AtomicUsize::new(0));
+        for mut stream in listener.incoming().map_while(Result::ok) {
+            if active.load(Ordering::SeqCst) >= MAX_RCON_CONNECTIONS {
+                let _ = stream.write_all(b"Too many admin connections, try again later\n");
+                continue;
+            }
+            active.fetch_add(1, Ordering::SeqCst);
+
+            let requests = requests.clone();
+            let password = password.clone();
+            let failures = failures.clone();
+            let active = active.clone();
+            thread::spawn(move || {
+                if let Err(e) = serve_remote_admin(stream, &password, &failures, requests) {
+                    warn!("Remote admin connection failed: {e}");
+                }
+                active.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+}
+
+/// A line-based session: the first line is the password, then each line is a command
+/// answered with its output
+fn serve_remote_admin(
+    stream: TcpStream,
+    password: &str,
+    failures: &LoginFailures,
+    requests: mpsc::Sender<AdminRequest>,
+) -> io::Result<()> {
+    let peer = stream.peer_addr()?;
+    let mut writer = stream.try_clone()?;
+
+    // Clones share the socket, so this covers the reader too
+    writer.set_read_timeout(Some(RCON_LOGIN_TIMEOUT))?;
+    let mut lines = BufReader::new(stream).lines();
+
+    writer.write_all(b"Password: ")?;
+    let attempt = lines.next().transpose()?.unwrap_or_default();
+    let matched = passwords_match(attempt.trim_end(), password);
+    match failures.try_login(peer.ip(), matched) {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!("Failed remote admin login from {peer}");
+            thread::sleep(RCON_LOGIN_FAILURE_DELAY);
+            writer.write_all(b"Wrong password\n")?;
+            return Ok(());
+        }
+        Err(wait) => {
+            warn!("Refused remote admin login from {peer}, too many failures");
+            let secs = wait.as_secs() + 1;
+            writeln!(writer, "Too many failed logins, try again in {secs}s")?;
+            return Ok(());
+        }
+    }
+    writer.set_read_timeout(None)?;
+
+    info!("Remote admin logged in from {peer}");
+    writeln!(writer, "Logged in, type help for commands")?;
+
+    for line in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        info!("Remote admin {peer}: {line}");
+        let (reply, replies) = mpsc::channel();
+        let request = AdminRequest {
+            line,
+            reply: Reply::Remote(reply),
+        };
+        if requests.send(request).is_err() {
+            break;
+        }
+        let output = replies
+            .recv_timeout(RCON_REPLY_TIMEOUT)
+            .unwrap_or_else(|_| "No reply from the server".to_string());
+        writeln!(writer, "{output}")?;
+    }
+
+    info!("Remote admin {peer} disconnected");
+    Ok(())
+}
+
+/// Failed remote admin logins by address, shared by every connection so that opening more of
+/// them doesn't speed up guessing
+#[derive(Default)]
+struct LoginFailures(Mutex<HashMap<IpAddr, (u32, Instant)>>);
+
+impl LoginFailures {
+    /// Records the outcome of a login, returning whether it succeeded, or how much longer the
+    /// address has to wait if it's still locked out from earlier failures. Checking and
+    /// recording under one lock means parallel connections can't slip attempts in between.
+    fn try_login(&self, ip: IpAddr, matched: bool) -> Result<bool, Duration> {
+        let Ok(mut failures) = self.0.lock() else {
+            return Ok(false);
+        };
+
+        if let Some(wait) = failures
+            .get(&ip)
+            .and_then(|(count, last)| Self::wait_after(*count).checked_sub(last.elapsed()))
+        {
+            return Err(wait);
+        }
+        if matched {
+            failures.remove(&ip);
+            return Ok(true);
+        }
+
+        // Addresses that have long served their time are forgotten
+        failures.retain(|_, (count, last)| last.elapsed() < Self::wait_after(*count) * 2);
+        let (count, last) = failures.entry(ip).or_insert((0, Instant::now()));
+        *count += 1;
+        *last = Instant::now();
+        Ok(false)
+    }
+
+    fn wait_after(count: u32) -> Duration {
+        RCON_LOGIN_FAILURE_DELAY
+            .saturating_mul(2u32.saturating_pow(count.saturating_sub(1)))
+            .min(RCON_MAX_LOCKOUT)
+    }
+}
+
+/// Compares every byte of the password whatever the attempt, so timing gives away neither
+/// how much matched nor how long the password is
+fn passwords_match(attempt: &str, password: &str) -> bool {
+    let attempt = attempt.as_bytes();
+    password
+        .bytes()
+        .enumerate()
+        .fold(attempt.len() ^ password.len(), |diff, (i, b)| {
+            diff | usize::from(attempt.get(i).copied().unwrap_or(0) ^ b)
+        })
+        == 0
+}
+
+/// Turns lines from the console and remote admins into commands for the systems below
+fn read_admin_input(inbox: Res<AdminInbox>, mut commands: EventWriter<AdminCommand>) {
+    let Ok(inbox) = inbox.0.lock() else {
+        return;
+    };
+
+    for AdminRequest { line, reply } in inbox.try_iter() {
+        match Command::parse(&line) {
+            Ok(Some(Command::Help)) => reply.send(HELP.to_string()),
+            Ok(Some(command)) => {
+                commands.write(AdminCommand { command, reply });
+            }
+            Ok(None) => {}
+            Err(e) => reply.send(e),
+        }
+    }
+}
+
+// === Commands ===
+#[allow(
+    clippy::too_many_arguments,
+    reason = "every player command is handled in one place, each needing its own resources"
+)]
+fn player_commands(
+    mut events: EventReader<AdminCommand>,
+    mut network: ServerNetwork,
+    transport: Res<NetcodeServerTransport>,
+    mut bans: ResMut<BanList>,
+    mut kicks: ResMut<Kicks>,
+    watch: Res<CheatWatch>,
+    spectators: Res<Spectators>,
+    players: Query<(&Player, &PlayerProfile, &Score, Has<DisconnectedPlayer>)>,
+) {
+    for AdminCommand { command, reply } in events.read() {
+        match command {
+            Command::Players => {
+                let mut lines: Vec<String> = players
+                    .iter()
+                    .map(|(player, profile, score, disconnected)| {
+                        let id = player.client_id;
+                        let status = match network.rtt(id) {
+                            _ if disconnected => "disconnected".to_string(),
+                            Some(rtt) => format!("{:.0} ms", rtt * 1000.0),
+                            None => "-".to_string(),
+                        };
+                        let ip =
+                            client_ip(&transport, id).map_or("-".to_string(), |ip| ip.to_string());
+                        format!(
+                            "{id:>16}  {:<16}  {:>4}  {status:>12}  {ip}",
+                            profile.name, score.0
+                        )
+                    })
+                    .collect();
+                lines.extend(spectators.0.iter().map(|id| {
+                    let ping = network
+                        .rtt(*id)
+                        .map_or("-".to_string(), |rtt| format!("{:.0} ms", rtt * 1000.0));
+                    let ip =
+                        client_ip(&transport, *id).map_or("-".to_string(), |ip| ip.to_string());
+                    format!(
+                        "{id:>16}  {:<16}  {:>4}  {ping:>12}  {ip}",
+                        "(spectator)", "-"
+                    )
+                }));
+                lines.sort();
+                reply.send(match lines.is_empty() {
+                    true => "No players".to_string(),
+                    false => lines.join("\n"),
+                });
+            }
+
+            Command::Kick { client_id, reason } => {
+                if !network.clients_id().contains(client_id) {
+                    reply.send(format!("No connected client {client_id}"));
+                    continue;
+                }
+                kicks.kick(&mut network, *client_id, reason);
+                reply.send(format!("Kicked {client_id}"));
+            }
+
+            Command::Ban(target) => {
+                let ip = match target {
+                    BanTarget::Ip(ip) => *ip,
+                    BanTarget::Client(client_id) => match client_ip(&transport, *client_id) {
+                        Some(ip) => ip,
+                        None => {
+                            reply.send(format!("No connected client {client_id}"));
+                            continue;
+                        }
+                    },
+                };
+
+                bans.add(ip);
+                let banned: Vec<u64> = network
+                    .clients_id()
+                    .into_iter()
+                    .filter(|id| client_ip(&transport, *id) == Some(ip))
+                    .collect();
+                for client_id in &banned {
+                    kicks.kick(&mut network, *client_id, "You are banned from this server");
+                }
+                info!("Banned {ip}");
+                reply.send(format!("Banned {ip}, kicked {} clients", banned.len()));
+            }
+
+            Command::Unban(ip) => {
+                let text = match bans.remove(*ip) {
+                    true => format!("Unbanned {ip}"),
+                    false => format!("{ip} wasn't banned"),
+                };
+                reply.send(text);
+            }
+
+            Command::Bans => reply.send(match bans.is_empty() {
+                true => "No bans".to_string(),
+                false => bans.list(),
+            }),
+
+            Command::Suspects => {
+                let lines: Vec<String> = watch
+                    .suspects()
+                    .into_iter()
+                    .map(|(client_id, suspicion, reason)| {
+                        let name = players
+                            .iter()
+                            .find(|(player, ..)| player.client_id == client_id)
+                            .map_or("-", |(_, profile, ..)| profile.name.as_str());
+                        let mark = if suspicion >= SUSPICION_THRESHOLD {
+                            "!"
+                        } else {
+                            " "
+                        };
+                        format!(
+                            "{mark}{client_id:>16}  {name:<16}  {suspicion:>5.1}  {}",
+                            reason.unwrap_or("-")
+                        )
+                    })
+                    .collect();
+                reply.send(match lines.is_empty() {
+                    true => "No suspects".to_string(),
+                    false => lines.join("\n"),
+                });
+            }
+
+            Command::Say(text) => {
+                network.broadcast(&ServerMessage::Chat {
+                    scope: ChatScope::Server,
+                    from: String::new(),
+                    text: format!("[Server] {text}"),
+                });
+                reply.send("Announced".to_string());
+            }
+
+            _ => {}
+        }
+    }
+}
+
+fn server_commands(
+    mut events: EventReader<AdminCommand>,
+    mut tick_rate: ResMut<TickRate>,
+    mut rules: ResMut<MatchRules>,
+) {
+    for AdminCommand { command, reply } in events.read() {
+        match command {
+            Command::TickRate(rate) => {
+                tick_rate.0 = *rate;
+                info!("Tick rate set to {rate} Hz");
+                reply.send(format!("Tick rate set to {rate} Hz"));
+            }
+
+            Command::Rules(None) => reply.send(rules.summary()),
+
+            Command::Rules(Some((name, value))) => match rules.set(name, value) {
+                Ok(()) => {
+                    info!("Match rule {name} set to {value}");
+                    reply.send(format!("{name} set to {value}"));
+                }
+                Err(e) => reply.send(e),
+            },
+
+            _ => {}
+        }
+    }
+}
+
+#[allow(
+    clippy::too_many_arguments,
+    reason = "switching maps and respawning touch most of the world"
+)]
+fn world_commands(
+    mut events: EventReader<AdminCommand>,
+    mut commands: Commands,
+    mut network: ServerNetwork,
+    mut map: ResMut<MapDefinition>,
+    rules: Res<MatchRules>,
+    mut spawner: ResMut<CollectibleSpawner>,
+    mut collectible_entities: ResMut<CollectibleEntityMap>,
+    mut ids: ResMut<NetworkIds>,
+    mut rng: ResMut<GameRng>,
+    boxes: Query<(Entity, &CollectibleId), With<BoxCollectable>>,
+    solids: Query<Entity, With<MapSolid>>,
+    mut players: Query<(&mut Transform, &mut Velocity), With<Player>>,
+) {
+    for AdminCommand { command, reply } in events.read() {
+        match command {
+            Command::Respawn => {
+                respawn_collectibles(
+                    &mut commands,
+                    &mut network,
+                    &mut collectible_entities,
+                    &mut spawner,
+                    &mut ids,
+                    &mut rng,
+                    &map,
+                    &boxes,
+                );
+                reply.send("Collectibles respawned".to_string());
+            }
+
+            Command::Map(path) => {
+                let mut next = match MapDefinition::load(path) {
+                    Ok(next) => next,
+                    Err(e) => {
+                        reply.send(format!("Failed to load map {path}: {e}"));
+                        continue;
+                    }
+                };
+                if let Err(e) = prepare_map(&mut next, rules.mode) {
+                    reply.send(e);
+                    continue;
+                }
+
+                for entity in solids.iter() {
+                    commands.entity(entity).despawn();
+                }
+                spawn_solids(&mut commands, &next);
+                network.broadcast(&ServerMessage::MapData(next.clone()));
+                respawn_collectibles(
+                    &mut commands,
+                    &mut network,
+                    &mut collectible_entities,
+                    &mut spawner,
+                    &mut ids,
+                    &mut rng,
+                    &next,
+                    &boxes,
+                );
+
+                // Everyone moves to the new spawn, in case a wall now stands where they were
+                let count = players.iter().len();
+                for (i, (mut transform, mut velocity)) in players.iter_mut().enumerate() {
+                    let offset = (i as f32 - (count as f32 - 1.0) / 2.0) * SPAWN_SPACING;
+                    transform.translation =
+                        (next.player_spawn + Vec2::new(offset, 0.0)).extend(0.0);
+                    *velocity = Velocity::zero();
+                }
+
+                info!("Switched to map {}", next.name);
+                reply.send(format!("Switched to map {}", next.name));
+                *map = next;
+            }
+
+            _ => {}
+        }
+    }
+}
+
+/// Disconnects kicked clients once their reason has gone out. Their player goes with them
+/// rather than being held, so a kicked client can't resume its session straight away.
+fn disconnect_kicked(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut kicks: ResMut<Kicks>,
+    mut network: ServerNetwork,
+    mut player_map: ResMut<PlayerEntityMap>,
+    mut sessions: ResMut<SessionMap>,
+    players: Query<(Entity, &Player, &SessionToken)>,
+) {
+    kicks.0.retain_mut(|(client_id, timer)| {
+        if !timer.tick(time.delta()).finished() {
+            return true;
+        }
+
+        let player = player_map
+            .0
+            .get(client_id)
+            .and_then(|entity| players.get(*entity).ok());
+        if let Some(player) = player {
+            drop_player(
+                &mut commands,
+                &mut network,
+                &mut player_map,
+                &mut sessions,
+                player,
+            );
+        }
+        network.disconnect(*client_id);
+        false
+    });
+}
+
+pub fn client_ip(transport: &NetcodeServerTransport, client_id: u64) -> Option<IpAddr> {
+    transport.client_addr(client_id).map(|(_, addr)| addr.ip())
+}
+
+// === Components and Resources ===
+/// Where admin commands come from, set from the command line
+#[derive(Resource)]
+pub struct AdminConfig {
+    /// Read commands from stdin
+    pub console: bool,
+    /// Address to accept remote admin connections on
+    pub rcon: Option<String>,
+    pub rcon_password: Option<String>,
+}
+
+impl AdminConfig {
+    pub fn from_args(args: &[String]) -> Self {
+        Self {
+            console: !args.iter().any(|arg| arg == "--no-console"),
+            rcon: arg_value(args, "--rcon").map(String::from),
+            rcon_password: arg_value(args, "--rcon-password")
+                .map(String::from)
+                .or_else(|| std::env::var("RCON_PASSWORD").ok()),
+        }
+    }
+}
+
+/// A line typed by an admin, with where to send the output
+struct AdminRequest {
+    line: String,
+    reply: Reply,
+}
+
+#[derive(Resource)]
+struct AdminInbox(Mutex<mpsc::Receiver<AdminRequest>>);
+
+enum Reply {
+    Console,
+    Remote(mpsc::Sender<String>),
+}
+
+impl Reply {
+    fn send(&self, text: String) {
+        match self {
+            Reply::Console => println!("{text}"),
+            // The admin may have hung up already; nothing to do about it
+            Reply::Remote(sender) => {
+                let _ = sender.send(text);
+            }
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct AdminCommand {
+    command: Command,
+    reply: Reply,
+}
+
+enum Command {
+    Help,
+    Players,
+    Kick { client_id: u64, reason: String },
+    Ban(BanTarget),
+    Unban(IpAddr),
+    Bans,
+    Suspects,
+    Say(String),
+    TickRate(u32),
+    Respawn,
+    Map(String),
+    Rules(Option<(String, String)>),
+}
+
+enum BanTarget {
+    Client(u64),
+    Ip(IpAddr),
+}
+
+impl Command {
+    /// `Ok(None)` for a blank line
+    fn parse(line: &str) -> Result<Option<Self>, String> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        let client_id = |arg: &str| {
+            arg.parse::<u64>()
+                .map_err(|_| format!("{arg:?} isn't a client ID"))
+        };
+        let ip = |arg: &str| {
+            arg.parse::<IpAddr>()
+                .map_err(|_| format!("{arg:?} isn't an IP address"))
+        };
+
+        let command = match name {
+            "" => return Ok(None),
+            "help" => Command::Help,
+            "players" => Command::Players,
+            "kick" => {
+                let (id, reason) = rest.split_once(' ').unwrap_or((rest, ""));
+                Command::Kick {
+                    client_id: client_id(id)?,
+                    reason: match reason.trim() {
+                        "" => "Kicked by an admin".to_string(),
+                        reason => reason.to_string(),
+                    },
+                }
+            }
+            "ban" => match ip(rest) {
+                Ok(ip) => Command::Ban(BanTarget::Ip(ip)),
+                Err(_) => Command::Ban(BanTarget::Client(client_id(rest)?)),
+            },
+            "unban" => Command::Unban(ip(rest)?),
+            "bans" => Command::Bans,
+            "suspects" => Command::Suspects,
+            "say" if !rest.is_empty() => Command::Say(rest.to_string()),
+            "tickrate" => Command::TickRate(
+                rest.parse()
+                    .ok()
+                    .filter(|rate| (1..=1000).contains(rate))
+                    .ok_or("Usage: tickrate <1-1000>")?,
+            ),
+            "respawn" => Command::Respawn,
+            "map" if !rest.is_empty() => Command::Map(rest.to_string()),
+            "rules" if rest.is_empty() => Command::Rules(None),
+            "rules" => {
+                let (name, value) = rest.split_once(' ').ok_or("Usage: rules <name> <value>")?;
+                Command::Rules(Some((name.to_string(), value.trim().to_string())))
+            }
+            _ => return Err(format!("Unknown command {line:?}, type help for commands")),
+        };
+        Ok(Some(command))
+    }
+}
+
+/// Addresses refused on connect, kept in a file with one address per line so bans outlast
+/// the server. The file is read again whenever it changes, so it can also be edited by hand.
+#[derive(Resource)]
+pub struct BanList {
+    path: PathBuf,
+    ips: BTreeSet<IpAddr>,
+    /// Modification time of the file when it was last read or written
+    modified: Option<SystemTime>,
+}
+
+impl BanList {
+    pub fn from_args(args: &[String]) -> Self {
+        let path = arg_value(args, "--ban-file").unwrap_or(DEFAULT_BAN_FILE);
+        let mut bans = Self {
+            path: PathBuf::from(path),
+            ips: BTreeSet::new(),
+            modified: None,
+        };
+        bans.refresh();
+        if !bans.is_empty() {
+            info!("Loaded {} bans from {path}", bans.ips.len());
+        }
+        bans
+    }
+
+    /// Reads the file again if it changed since we last saw it
+    pub fn refresh(&mut self) {
+        let modified = fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        if modified.is_none() || modified == self.modified {
+            return;
+        }
+
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                error!("Failed to read ban list {}: {e}", self.path.display());
+                return;
+            }
+        };
+        self.ips = contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| match line.parse() {
+                Ok(ip) => Some(ip),
+                Err(_) => {
+                    warn!("Ignoring {line:?} in ban list, it isn't an IP address");
+                    None
+                }
+            })
+            .collect();
+        self.modified = modified;
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.ips.contains(&ip)
+    }
+
+    pub fn add(&mut self, ip: IpAddr) {
+        self.refresh();
+        if self.ips.insert(ip) {
+            self.save();
+        }
+    }
+
+    /// Returns whether the address was banned
+    pub fn remove(&mut self, ip: IpAddr) -> bool {
+        self.refresh();
+        let removed = self.ips.remove(&ip);
+        if removed {
+            self.save();
+        }
+        removed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ips.is_empty()
+    }
+
+    /// One address per line
+    pub fn list(&self) -> String {
+        self.ips
+            .iter()
+            .map(|ip| ip.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn save(&mut self) {
+        let mut contents = self.list();
+        contents.push('\n');
+        if let Err(e) = fs::write(&self.path, contents) {
+            error!("Failed to save ban list {}: {e}", self.path.display());
+            return;
+        }
+        self.modified = fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok();
+    }
+}
+
+/// Clients told they've been kicked, waiting to be disconnected
+#[derive(Resource, Default)]
+pub struct Kicks(Vec<(u64, Timer)>);
+
+impl Kicks {
+    pub fn kick(&mut self, network: &mut ServerNetwork, client_id: u64, reason: &str) {
+        if self.contains(client_id) {
+            return;
+        }
+
+        info!("Kicking {client_id}: {reason}");
+        network.send(
+            client_id,
+            &ServerMessage::Kicked {
+                reason: reason.to_string(),
+            },
+        );
+        self.0.push((
+            client_id,
+            Timer::from_seconds(KICK_DELAY_SECS, TimerMode::Once),
+        ));
+    }
+
+    /// Whether the client has been kicked and is about to be disconnected
+    pub fn contains(&self, client_id: u64) -> bool {
+        self.0.iter().any(|(kicked, _)| *kicked == client_id)
+    }
+}
+
+/// What connection handling needs to turn away unwanted clients
+#[derive(SystemParam)]
+pub struct Moderation<'w> {
+    time: Res<'w, Time>,
+    bans: ResMut<'w, BanList>,
+    throttle: ResMut<'w, ConnectThrottle>,
+    kicks: ResMut<'w, Kicks>,
+}
+
+impl Moderation<'_> {
+    /// Kicks the client if its connect data is unusable, or its address is banned or has been
+    /// connecting too often, returning whether it was
+    pub fn refuse(
+        &mut self,
+        network: &mut ServerNetwork,
+        transport: &NetcodeServerTransport,
+        client_id: u64,
+    ) -> bool {
+        let connect_data = transport
+            .user_data(client_id)
+            .and_then(|data| ConnectData::from_user_data(&data));
+        if connect_data.is_none() {
+            info!("Refusing client {client_id}, its connect data is invalid");
+            self.kicks.kick(
+                network,
+                client_id,
+                &format!("Names must be 1 to {MAX_NAME_LEN} characters"),
+            );
+            return true;
+        }
+
+        let Some(ip) = client_ip(transport, client_id) else {
+            return false;
+        };
+
+        self.bans.refresh();
+        if self.bans.contains(ip) {
+            info!("Refusing client {client_id} from banned address {ip}");
+            self.kicks
+                .kick(network, client_id, "You are banned from this server");
+            return true;
+        }
+
+        if !self.throttle.allow(ip, self.time.elapsed_secs()) {
+            info!("Refusing client {client_id}, {ip} is connecting too often");
+            self.kicks.kick(
+                network,
+                client_id,
+                "Too many connection attempts, try again in a minute",
+            );
+            return true;
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_their_arguments() {
+        assert!(matches!(Command::parse("   "), Ok(None)));
+        assert!(matches!(
+            Command::parse(" players "),
+            Ok(Some(Command::Players))
+        ));
+        assert!(matches!(
+            Command::parse("kick 42"),
+            Ok(Some(Command::Kick { client_id: 42, reason })) if reason == "Kicked by an admin"
+        ));
+        assert!(matches!(
+            Command::parse("kick 42  being rude "),
+            Ok(Some(Command::Kick { client_id: 42, reason })) if reason == "being rude"
+        ));
+        assert!(matches!(
+            Command::parse("ban 7"),
+            Ok(Some(Command::Ban(BanTarget::Client(7))))
+        ));
+        assert!(matches!(
+            Command::parse("ban 10.0.0.1"),
+            Ok(Some(Command::Ban(BanTarget::Ip(ip)))) if ip == IpAddr::from([10, 0, 0, 1])
+        ));
+        assert!(matches!(
+            Command::parse("tickrate 30"),
+            Ok(Some(Command::TickRate(30)))
+        ));
+    }
+
+    #[test]
+    fn rules_command_changes_the_match_rules() {
+        let Ok(Some(Command::Rules(Some((name, value))))) = Command::parse("rules round-secs 90")
+        else {
+            panic!("rules with a name and value should parse");
+        };
+        let mut rules = MatchRules::from_args(&[]).unwrap();
+        rules.set(&name, &value).unwrap();
+        assert_eq!(rules.round, Duration::from_secs(90));
+
+        let Ok(Some(Command::Rules(Some((name, value))))) = Command::parse("rules round-secs -1")
+        else {
+            panic!("rules with a name and value should parse");
+        };
+        assert!(rules.set(&name, &value).is_err());
+        assert_eq!(rules.round, Duration::from_secs(90));
+    }
+
+    #[test]
+    fn rejects_bad_arguments_and_unknown_commands() {
+        for line in [
+            "kick",
+            "kick bob",
+            "ban",
+            "unban 7",
+            "tickrate 0",
+            "tickrate 1001",
+            "say",
+            "map",
+            "rules round-secs",
+            "shutdown",
+        ] {
+            assert!(Command::parse(line).is_err(), "{line:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn passwords_must_match_exactly() {
+        assert!(passwords_match("hunter2", "hunter2"));
+        assert!(!passwords_match("hunter3", "hunter2"));
+        assert!(!passwords_match("hunter", "hunter2"));
+        assert!(!passwords_match("hunter22", "hunter2"));
+        assert!(!passwords_match("hunter2\0", "hunter2"));
+        assert!(!passwords_match("", "hunter2"));
+    }
+
+    #[test]
+    fn login_wait_doubles_up_to_the_lockout() {
+        assert_eq!(LoginFailures::wait_after(1), RCON_LOGIN_FAILURE_DELAY);
+        assert_eq!(LoginFailures::wait_after(2), RCON_LOGIN_FAILURE_DELAY * 2);
+        assert_eq!(LoginFailures::wait_after(3), RCON_LOGIN_FAILURE_DELAY * 4);
+        assert_eq!(LoginFailures::wait_after(u32::MAX), RCON_MAX_LOCKOUT);
+    }
+
+    #[test]
+    fn failed_logins_lock_out_only_that_address() {
+        let failures = LoginFailures::default();
+        let attacker = IpAddr::from([10, 0, 0, 1]);
+        let admin = IpAddr::from([10, 0, 0, 2]);
+
+        assert_eq!(failures.try_login(attacker, false), Ok(false));
+        // Even the right password is turned away while locked out
+        assert!(failures.try_login(attacker, true).is_err());
+        assert_eq!(failures.try_login(admin, true), Ok(true));
+    }
+}
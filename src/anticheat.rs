@@ -0,0 +1,173 @@
+use crate::{protocol::INPUT_INTERVAL_SECS, server::PlayerEntityMap};
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_renet2::prelude::RenetServer;
+
+/// Seconds of traffic looked at together. Long enough that inputs held up by the network
+/// and then arriving in a bunch don't look like a speed hack.
+const REVIEW_SECS: f32 = 5.0;
+
+/// How much more input time than real time a client may claim, for jitter and clock drift
+const INPUT_TIME_TOLERANCE: f32 = 1.25;
+
+/// How much faster than the fixed input rate a client may seem to send, for inputs held up by
+/// the network arriving in a bunch
+const INPUT_RATE_TOLERANCE: f32 = 1.5;
+
+/// Collection attempts out of reach allowed per review, for boxes that moved or lag
+const ALLOWED_COLLECT_MISSES: u32 = 5;
+
+/// Suspicion that wears off per second of good behaviour
+const SUSPICION_DECAY_PER_SEC: f32 = 0.05;
+
+/// Suspicion at which a client is worth an admin's attention
+pub const SUSPICION_THRESHOLD: f32 = 3.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CheatWatch>()
+        .add_systems(Update, (review_clients, forget_departed));
+}
+
+// === Anti-cheat ===
+/// Compares what each client sent over the last few seconds against what an honest client
+/// could send, and raises the suspicion of any that don't add up
+fn review_clients(time: Res<Time>, mut watch: ResMut<CheatWatch>, mut last_review: Local<f32>) {
+    let now = time.elapsed_secs();
+    if now - *last_review < REVIEW_SECS {
+        return;
+    }
+    *last_review = now;
+
+    for (client_id, client) in watch.0.iter_mut() {
+        let elapsed = now - client.window_start;
+        if elapsed <= 0.0 {
+            continue;
+        }
+
+        let mut flags = Vec::new();
+
+        // Moves are scaled by the frame time the client reports, so claiming more time than
+        // has passed moves a player faster than anyone else
+        let allowed_input_time = elapsed * INPUT_TIME_TOLERANCE;
+        if client.input_time > allowed_input_time {
+            let excess = client.input_time / elapsed - 1.0;
+            flags.push((
+                excess * 4.0,
+                format!(
+                    "claimed {:.1}s of input in {elapsed:.1}s",
+                    client.input_time
+                ),
+            ));
+        }
+
+        let input_rate = client.inputs as f32 / elapsed;
+        let allowed_input_rate = INPUT_RATE_TOLERANCE / INPUT_INTERVAL_SECS;
+        if input_rate > allowed_input_rate {
+            flags.push((
+                input_rate / allowed_input_rate - 1.0,
+                format!(
+                    "sent {input_rate:.0} inputs/s, clients send {:.0}",
+                    1.0 / INPUT_INTERVAL_SECS
+                ),
+            ));
+        }
+
+        // An honest client only asks for boxes it's touching, so one asking for boxes far away
+        // is reading the box list to vacuum the map
+        if client.collect_misses > ALLOWED_COLLECT_MISSES {
+            flags.push((
+                (client.collect_misses - ALLOWED_COLLECT_MISSES) as f32 * 0.2,
+                format!(
+                    "tried to collect {} boxes out of reach",
+                    client.collect_misses
+                ),
+            ));
+        }
+
+        client.suspicion = (client.suspicion - SUSPICION_DECAY_PER_SEC * elapsed).max(0.0);
+        for (weight, reason) in flags {
+            client.suspicion += weight;
+            warn!(
+                "Client {client_id} looks suspicious, {reason} (suspicion {:.1})",
+                client.suspicion
+            );
+            client.last_reason = Some(reason);
+        }
+        if client.suspicion == 0.0 {
+            client.last_reason = None;
+        }
+
+        client.inputs = 0;
+        client.input_time = 0.0;
+        client.collect_misses = 0;
+        client.window_start = now;
+    }
+}
+
+/// Forgets clients once they've left and no player is held for them to come back to, so
+/// reconnecting doesn't wipe a record clean
+fn forget_departed(
+    server: Res<RenetServer>,
+    player_map: Res<PlayerEntityMap>,
+    mut watch: ResMut<CheatWatch>,
+) {
+    watch.0.retain(|client_id, _| {
+        server.is_connected(*client_id) || player_map.0.contains_key(client_id)
+    });
+}
+
+// === Components and Resources ===
+/// What a client has sent since its last review, and how suspicious it looks so far
+#[derive(Default)]
+struct ClientWatch {
+    window_start: f32,
+    inputs: u32,
+    /// Sum of the frame times the client reported with its inputs
+    input_time: f32,
+    collect_misses: u32,
+    suspicion: f32,
+    last_reason: Option<String>,
+}
+
+/// Per client statistics, fed by message handling and reviewed every few seconds
+#[derive(Resource, Default)]
+pub struct CheatWatch(HashMap<u64, ClientWatch>);
+
+impl CheatWatch {
+    pub fn record_input(&mut self, client_id: u64, delta: f32, now: f32) {
+        let client = self.client(client_id, now);
+        client.inputs += 1;
+        client.input_time += delta.max(0.0);
+    }
+
+    pub fn record_collect_miss(&mut self, client_id: u64, now: f32) {
+        self.client(client_id, now).collect_misses += 1;
+    }
+
+    /// Carries a record over to the new client ID of a resumed session
+    pub fn resume(&mut self, old_client_id: u64, client_id: u64) {
+        if let Some(client) = self.0.remove(&old_client_id) {
+            self.0.insert(client_id, client);
+        }
+    }
+
+    /// Clients with any suspicion, most suspicious first, with the latest reason
+    pub fn suspects(&self) -> Vec<(u64, f32, Option<&str>)> {
+        let mut suspects: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(_, client)| client.suspicion > 0.0)
+            .map(|(client_id, client)| {
+                (*client_id, client.suspicion, client.last_reason.as_deref())
+            })
+            .collect();
+        suspects.sort_by(|a, b| b.1.total_cmp(&a.1));
+        suspects
+    }
+
+    fn client(&mut self, client_id: u64, now: f32) -> &mut ClientWatch {
+        self.0.entry(client_id).or_insert_with(|| ClientWatch {
+            window_start: now,
+            ..default()
+        })
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{
+    ServerMessage, arg_value,
+    inventory::{Inventory, scatter_items},
+    map::MapDefinition,
+    powerups::ActiveEffects,
+    protocol::{PlayerProfile, PowerUp},
+    rounds::{MatchRules, MatchState},
+    server::{CollectibleEntityMap, DisconnectedPlayer, NetworkIds, Player, Score, ServerNetwork},
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_rapier2d::prelude::*;
+use std::{fmt::Display, str::FromStr};
+
+/// Seconds before the same two players can bump each other again
+const BUMP_COOLDOWN_SECS: f32 = 0.75;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, handle_bumps).add_systems(
+        PostUpdate,
+        remember_velocities.before(PhysicsSet::StepSimulation),
+    );
+}
+
+// === Bumps ===
+/// Keeps each player's velocity from just before the physics step, since by the time a
+/// collision is reported the solver has already bounced them apart
+fn remember_velocities(mut players: Query<(&Velocity, &mut PreStepVelocity)>) {
+    for (velocity, mut pre_step) in players.iter_mut() {
+        pre_step.0 = velocity.linvel;
+    }
+}
+
+/// The faster of two colliding players knocks the other back and takes some of their points.
+/// When boxes are carried the victim drops some of theirs instead, for anyone to grab, and
+/// teammates don't knock anything loose from each other.
+#[allow(
+    clippy::too_many_arguments,
+    clippy::type_complexity,
+    reason = "bumps move, score and drop boxes for both players involved"
+)]
+fn handle_bumps(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionEvent>,
+    time: Res<Time>,
+    rules: Res<CollisionRules>,
+    match_rules: Res<MatchRules>,
+    match_state: Res<MatchState>,
+    map: Res<MapDefinition>,
+    mut collectible_entities: ResMut<CollectibleEntityMap>,
+    mut ids: ResMut<NetworkIds>,
+    mut network: ServerNetwork,
+    mut last_bumps: Local<HashMap<(Entity, Entity), f32>>,
+    mut players: Query<
+        (
+            &Player,
+            &PlayerProfile,
+            &Transform,
+            &PreStepVelocity,
+            &mut Velocity,
+            (&mut Score, &mut Inventory),
+            &ActiveEffects,
+        ),
+        Without<DisconnectedPlayer>,
+    >,
+) {
+    let now = time.elapsed_secs();
+    last_bumps.retain(|_, at| now - *at < BUMP_COOLDOWN_SECS);
+
+    for event in collisions.read() {
+        let CollisionEvent::Started(a, b, _) = event else {
+            continue;
+        };
+        let pair = (*a.min(b), *a.max(b));
+        if last_bumps.contains_key(&pair) {
+            continue;
+        }
+
+        let Ok(
+            [
+                (_, _, first, first_velocity, ..),
+                (_, _, second, second_velocity, ..),
+            ],
+        ) = players.get_many([*a, *b])
+        else {
+            continue;
+        };
+
+        // How fast each was driving into the other
+        let normal = (second.translation - first.translation)
+            .truncate()
+            .normalize_or_zero();
+        let first_speed = first_velocity.0.dot(normal);
+        let second_speed = -second_velocity.0.dot(normal);
+        if first_speed.max(second_speed) < rules.min_bump_speed {
+            continue;
+        }
+        last_bumps.insert(pair, now);
+
+        let (bumper, victim, direction) = if first_speed >= second_speed {
+            (*a, *b, normal)
+        } else {
+            (*b, *a, -normal)
+        };
+        let Ok(
+            [
+                (bumper, bumper_profile, _, _, _, (mut bumper_score, _), _),
+                (
+                    victim,
+                    victim_profile,
+                    victim_transform,
+                    _,
+                    mut victim_velocity,
+                    (mut victim_score, mut victim_inventory),
+                    victim_effects,
+                ),
+            ],
+        ) = players.get_many_mut([bumper, victim])
+        else {
+            continue;
+        };
+
+        // A shield shrugs off both the knockback and the theft
+        if victim_effects.has(PowerUp::Shield) {
+            continue;
+        }
+
+        victim_velocity.linvel += direction * rules.knockback;
+
+        let teammates = bumper_profile.team.is_some() && bumper_profile.team == victim_profile.team;
+        if teammates || !match_state.allows_collecting() {
+            continue;
+        }
+
+        if match_rules.carries() {
+            let dropped = victim_inventory.take(rules.steal as usize);
+            if dropped.is_empty() {
+                continue;
+            }
+            info!(
+                "Player {} bumped {} and knocked {} items loose",
+                bumper.client_id,
+                victim.client_id,
+                dropped.len()
+            );
+
+            let spawned = scatter_items(
+                &mut commands,
+                &mut collectible_entities,
+                &mut ids,
+                &map,
+                victim_transform.translation.truncate(),
+                &dropped,
+            );
+            network.broadcast(&ServerMessage::SpawnCollectibles(spawned));
+            network.broadcast(&victim_inventory.message(victim.client_id));
+            continue;
+        }
+
+        let stolen = rules.steal.min(victim_score.0);
+        if stolen == 0 {
+            continue;
+        }
+
+        victim_score.0 -= stolen;
+        bumper_score.0 += stolen;
+        info!(
+            "Player {} bumped {} and stole {stolen}",
+            bumper.client_id, victim.client_id
+        );
+
+        network.broadcast(&ServerMessage::ScoreUpdate {
+            client_id: victim.client_id,
+            score: victim_score.0,
+        });
+        network.broadcast(&ServerMessage::ScoreUpdate {
+            client_id: bumper.client_id,
+            score: bumper_score.0,
+        });
+    }
+}
+
+// === Components and Resources ===
+#[derive(Component, Default)]
+pub struct PreStepVelocity(pub Vec2);
+
+/// How players interact when they run into each other, set from the command line
+#[derive(Resource)]
+pub struct CollisionRules {
+    /// Restitution of player bodies; 0 is a dead stop, 1 a perfect bounce
+    pub bounce: f32,
+    /// Speed added to a bumped player, away from the bumper
+    pub knockback: f32,
+    /// Closing speed needed for a collision to count as a bump
+    pub min_bump_speed: f32,
+    /// Points taken from a bumped player, or boxes they drop when boxes are carried.
+    /// 0 turns stealing off.
+    pub steal: u32,
+}
+
+impl CollisionRules {
+    /// Values that don't parse or are out of range are warned about and left at their defaults
+    pub fn from_args(args: &[String]) -> Self {
+        let speed = |speed: f32| speed.is_finite() && speed >= 0.0;
+
+        Self {
+            bounce: arg_or_default(args, "--bounce", 0.5, |bounce: f32| {
+                (0.0..=1.0).contains(&bounce)
+            }),
+            knockback: arg_or_default(args, "--knockback", 400.0, speed),
+            min_bump_speed: arg_or_default(args, "--bump-speed", 200.0, speed),
+            steal: arg_or_default(args, "--bump-steal", 1, |_| true),
+        }
+    }
+}
+
+/// The value given for `flag`, or `default` if there's none or it isn't valid
+fn arg_or_default<T: FromStr + Display + Copy>(
+    args: &[String],
+    flag: &str,
+    default: T,
+    valid: impl Fn(T) -> bool,
+) -> T {
+    let Some(text) = arg_value(args, flag) else {
+        return default;
+    };
+    match text.parse::<T>() {
+        Ok(value) if valid(value) => value,
+        _ => {
+            warn!("Ignoring {flag} {text:?}, using {default}");
+            default
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    BoxCollectable,
+    chat_box::typing,
+    client::{Player, RemotePlayer},
+    map::MapDefinition,
+    protocol::PlayerProfile,
+    screens::ClientState,
+    spectator_camera::spectating,
+};
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+
+/// How far the player can move from the center of the view before the camera follows
+const DEAD_ZONE: Vec2 = Vec2::new(120.0, 80.0);
+
+/// How quickly the camera catches up with where it should be
+const FOLLOW_SHARPNESS: f32 = 8.0;
+
+/// Floor shown past the arena bounds, enough to see the walls
+const MAP_MARGIN: f32 = 40.0;
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 3.0;
+
+/// Zoom change per line scrolled or key press
+const ZOOM_STEP: f32 = 1.1;
+
+/// Furthest away an off-screen collectible or teammate gets an indicator
+const INDICATOR_RANGE: f32 = 1500.0;
+
+/// Screen pixels between an indicator and the edge of the window
+const INDICATOR_MARGIN: f32 = 24.0;
+
+/// Length of an indicator arrow, in screen pixels
+const INDICATOR_SIZE: f32 = 16.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CameraZoom>()
+        .init_resource::<CameraFocus>()
+        .add_systems(OnEnter(ClientState::InGame), reset_camera_targets)
+        .add_systems(OnExit(ClientState::InGame), reset_camera)
+        .add_systems(
+            Update,
+            zoom_camera
+                .run_if(not(typing))
+                .run_if(in_state(ClientState::InGame)),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                follow_local_player.run_if(not(spectating)),
+                apply_zoom,
+                draw_offscreen_indicators.run_if(not(spectating)),
+            )
+                .chain()
+                .before(TransformSystem::TransformPropagate)
+                .run_if(in_state(ClientState::InGame)),
+        );
+}
+
+/// Where the camera's center may go without showing too much beyond the map
+pub fn clamp_to_map(center: Vec2, camera: &Camera, zoom: f32, map: Option<&MapDefinition>) -> Vec2 {
+    let (Some(map), Some(viewport)) = (map, camera.logical_viewport_size()) else {
+        return center;
+    };
+    // A map smaller than the view stays centered
+    let limit =
+        (map.half_extents + Vec2::splat(MAP_MARGIN) - viewport / 2.0 * zoom).max(Vec2::ZERO);
+    center.clamp(-limit, limit)
+}
+
+/// How far `current` moves towards its target this frame
+pub fn follow_blend(time: &Time) -> f32 {
+    1.0 - (-FOLLOW_SHARPNESS * time.delta_secs()).exp()
+}
+
+// === Camera ===
+fn reset_camera_targets(mut zoom: ResMut<CameraZoom>, mut focus: ResMut<CameraFocus>) {
+    *zoom = CameraZoom::default();
+    focus.0 = Vec2::ZERO;
+}
+
+/// Puts the camera back where the menus expect it
+fn reset_camera(mut cameras: Query<(&mut Transform, &mut Projection), With<Camera2d>>) {
+    for (mut transform, mut projection) in cameras.iter_mut() {
+        transform.translation = Vec3::ZERO;
+        if let Projection::Orthographic(ortho) = projection.as_mut() {
+            ortho.scale = 1.0;
+        }
+    }
+}
+
+/// The mouse wheel, or `-` and `=`, zoom out and in
+fn zoom_camera(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut zoom: ResMut<CameraZoom>,
+) {
+    let mut steps: f32 = wheel
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        })
+        .sum();
+    if keys.just_pressed(KeyCode::Equal) {
+        steps += 1.0;
+    }
+    if keys.just_pressed(KeyCode::Minus) {
+        steps -= 1.0;
+    }
+
+    if steps != 0.0 {
+        zoom.0 = (zoom.0 * ZOOM_STEP.powf(-steps)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+}
+
+/// Keeps our player inside a dead zone around the middle of the view, so small movements
+/// don't drag the camera around, and stops at the edges of the map
+#[allow(
+    clippy::type_complexity,
+    reason = "the filters keep the player and camera transforms apart"
+)]
+fn follow_local_player(
+    time: Res<Time>,
+    zoom: Res<CameraZoom>,
+    map: Option<Res<MapDefinition>>,
+    mut focus: ResMut<CameraFocus>,
+    local_player: Query<&Transform, (With<Player>, Without<RemotePlayer>, Without<Camera2d>)>,
+    mut view: Query<(&Camera, &mut Transform), With<Camera2d>>,
+) {
+    let (Ok(player), Ok((camera, mut transform))) = (local_player.single(), view.single_mut())
+    else {
+        return;
+    };
+
+    let offset = player.translation.truncate() - focus.0;
+    let outside = (offset.abs() - DEAD_ZONE).max(Vec2::ZERO);
+    focus.0 += outside * offset.signum();
+    focus.0 = clamp_to_map(focus.0, camera, zoom.0, map.as_deref());
+
+    let current = transform.translation.truncate();
+    transform.translation = current
+        .lerp(focus.0, follow_blend(&time))
+        .extend(transform.translation.z);
+}
+
+fn apply_zoom(
+    time: Res<Time>,
+    zoom: Res<CameraZoom>,
+    mut projections: Query<&mut Projection, With<Camera2d>>,
+) {
+    let blend = follow_blend(&time);
+    for mut projection in projections.iter_mut() {
+        if let Projection::Orthographic(ortho) = projection.as_mut() {
+            ortho.scale += (zoom.0 - ortho.scale) * blend;
+        }
+    }
+}
+
+/// Arrows along the edge of the view pointing at nearby collectibles and teammates that are
+/// out of sight
+#[allow(
+    clippy::type_complexity,
+    reason = "the filters tell our player apart from everyone else"
+)]
+fn draw_offscreen_indicators(
+    mut gizmos: Gizmos,
+    local_player: Query<
+        (&Transform, Option<&PlayerProfile>),
+        (With<Player>, Without<RemotePlayer>),
+    >,
+    teammates: Query<(&Transform, &PlayerProfile), With<RemotePlayer>>,
+    boxes: Query<(&Transform, &Sprite), With<BoxCollectable>>,
+    view: Query<(&Camera, &Transform, &Projection), With<Camera2d>>,
+) {
+    let (Ok((player, profile)), Ok((camera, camera_transform, projection))) =
+        (local_player.single(), view.single())
+    else {
+        return;
+    };
+    let (Some(viewport), Projection::Orthographic(ortho)) =
+        (camera.logical_viewport_size(), projection)
+    else {
+        return;
+    };
+
+    let scale = ortho.scale;
+    let center = camera_transform.translation.truncate();
+    let edge = (viewport / 2.0 - Vec2::splat(INDICATOR_MARGIN)).max(Vec2::ZERO) * scale;
+    let player_position = player.translation.truncate();
+    let our_team = profile.and_then(|profile| profile.team);
+
+    let targets = boxes
+        .iter()
+        .map(|(transform, sprite)| (transform.translation.truncate(), sprite.color))
+        .chain(
+            teammates
+                .iter()
+                .filter(|(_, other)| our_team.is_some() && other.team == our_team)
+                .map(|(transform, other)| (transform.translation.truncate(), other.color())),
+        );
+
+    for (target, color) in targets {
+        let offset = target - center;
+        let on_screen = offset.x.abs() <= edge.x && offset.y.abs() <= edge.y;
+        if on_screen || target.distance(player_position) > INDICATOR_RANGE {
+            continue;
+        }
+
+        // Scale the offset down until it touches the edge of the view
+        let fit = (edge / offset.abs()).min_element();
+        let tip = center + offset * fit;
+        let direction = offset.normalize_or_zero();
+        gizmos.arrow_2d(tip - direction * INDICATOR_SIZE * scale, tip, color);
+    }
+}
+
+// === Components and Resources ===
+/// How far out the camera is zoomed, 1 being the default
+#[derive(Resource)]
+pub struct CameraZoom(pub f32);
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Where the player's camera is headed, kept apart from the camera's transform so the
+/// dead zone doesn't drift as the camera eases
+#[derive(Resource, Default)]
+struct CameraFocus(Vec2);
@@ -0,0 +1,259 @@
+use crate::{
+    ServerMessage,
+    flood::{Allowance, Limit},
+    protocol::{ChatScope, MAX_CHAT_LEN, PlayerProfile},
+    server::{DisconnectedPlayer, Player, PlayerEntityMap, ServerNetwork, unique_profile},
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+
+/// Messages a player can send in a burst before being throttled, then one every 2 seconds
+const CHAT_LIMIT: Limit = Limit {
+    burst: 5.0,
+    per_sec: 0.5,
+};
+
+const HELP: &str = "Commands: /w <name> <message>, /t <message>, /name <new name>, /who";
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<ChatReceived>()
+        .add_systems(Update, handle_chat);
+}
+
+// === Chat ===
+/// Checks each message against the sender's allowance and the length limit, then either runs
+/// it as a command or passes it on to whoever its scope covers
+fn handle_chat(
+    mut events: EventReader<ChatReceived>,
+    time: Res<Time>,
+    player_map: Res<PlayerEntityMap>,
+    mut allowances: Local<HashMap<Entity, Allowance>>,
+    mut network: ServerNetwork,
+    mut players: Query<(Entity, &Player, &mut PlayerProfile, Has<DisconnectedPlayer>)>,
+) {
+    let now = time.elapsed_secs();
+    allowances.retain(|entity, _| players.contains(*entity));
+
+    for ChatReceived { client_id, text } in events.read() {
+        let client_id = *client_id;
+        let Some(sender) = player_map.0.get(&client_id).copied() else {
+            notice(
+                &mut network,
+                client_id,
+                "Spectators can read chat but not send it",
+            );
+            continue;
+        };
+
+        let allowance = allowances
+            .entry(sender)
+            .or_insert_with(|| Allowance::full(&CHAT_LIMIT, now));
+        if !allowance.spend(&CHAT_LIMIT, now) {
+            debug!("Dropped chat from {client_id}, sending too fast");
+            notice(&mut network, client_id, "You're sending messages too fast");
+            continue;
+        }
+
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        if text.chars().count() > MAX_CHAT_LEN {
+            notice(
+                &mut network,
+                client_id,
+                &format!("Messages can be at most {MAX_CHAT_LEN} characters"),
+            );
+            continue;
+        }
+
+        let Ok((_, _, profile, _)) = players.get(sender) else {
+            continue;
+        };
+        let from = profile.name.clone();
+        let team = profile.team;
+
+        let (command, rest) = match text.strip_prefix('/') {
+            Some(command) => command.split_once(' ').unwrap_or((command, "")),
+            None => ("", text),
+        };
+        let rest = rest.trim();
+
+        match command {
+            "" => {
+                info!("[All] {from}: {rest}");
+                network.broadcast(&ServerMessage::Chat {
+                    scope: ChatScope::All,
+                    from,
+                    text: rest.to_string(),
+                });
+            }
+
+            "t" | "team" => {
+                let Some(team) = team else {
+                    notice(&mut network, client_id, "You're not on a team");
+                    continue;
+                };
+                info!("[{team:?}] {from}: {rest}");
+                let message = ServerMessage::Chat {
+                    scope: ChatScope::Team(team),
+                    from,
+                    text: rest.to_string(),
+                };
+                for (_, player, profile, disconnected) in players.iter() {
+                    if profile.team == Some(team) && !disconnected {
+                        network.send(player.client_id, &message);
+                    }
+                }
+            }
+
+            "w" | "whisper" => {
+                let online = players
+                    .iter()
+                    .filter(|(.., disconnected)| !disconnected)
+                    .map(|(_, player, profile, _)| (player.client_id, profile.name.as_str()));
+                let target = whisper_target(rest, online)
+                    .map(|(target_id, to, message)| (target_id, to.to_string(), message));
+                let Some((target_id, to, message)) = target else {
+                    notice(&mut network, client_id, "No player with that name");
+                    continue;
+                };
+                if message.is_empty() {
+                    notice(&mut network, client_id, "Usage: /w <name> <message>");
+                    continue;
+                }
+
+                debug!("[Whisper] {from} -> {to}: {message}");
+                let message = ServerMessage::Chat {
+                    scope: ChatScope::Whisper { to },
+                    from,
+                    text: message.to_string(),
+                };
+                network.send(target_id, &message);
+                if target_id != client_id {
+                    network.send(client_id, &message);
+                }
+            }
+
+            "name" => {
+                let requested = PlayerProfile {
+                    name: rest.to_string(),
+                    ..default()
+                };
+                let others: Vec<PlayerProfile> = players
+                    .iter()
+                    .filter(|(entity, ..)| *entity != sender)
+                    .map(|(_, _, profile, _)| profile.clone())
+                    .collect();
+                let name = unique_profile(&requested, others.iter()).name;
+                if rest.is_empty() || name == from {
+                    notice(&mut network, client_id, "Usage: /name <new name>");
+                    continue;
+                }
+
+                if let Ok((_, _, mut profile, _)) = players.get_mut(sender) {
+                    profile.name = name.clone();
+                }
+                info!("Player {client_id} renamed from {from:?} to {name:?}");
+                network.broadcast(&ServerMessage::PlayerRenamed {
+                    client_id,
+                    name: name.clone(),
+                });
+                network.broadcast(&ServerMessage::Chat {
+                    scope: ChatScope::Server,
+                    from: String::new(),
+                    text: format!("{from} is now known as {name}"),
+                });
+            }
+
+            "who" => {
+                let mut names: Vec<String> = players
+                    .iter()
+                    .filter(|(.., disconnected)| !disconnected)
+                    .map(|(_, _, profile, _)| match profile.team {
+                        Some(team) => format!("{} [{team:?}]", profile.name),
+                        None => profile.name.clone(),
+                    })
+                    .collect();
+                names.sort();
+                notice(
+                    &mut network,
+                    client_id,
+                    &format!("{} online: {}", names.len(), names.join(", ")),
+                );
+            }
+
+            "help" => notice(&mut network, client_id, HELP),
+
+            _ => notice(
+                &mut network,
+                client_id,
+                &format!("Unknown command /{command}. {HELP}"),
+            ),
+        }
+    }
+}
+
+/// Splits `/w` arguments into who they're for and the message. Names can contain spaces, so
+/// this goes with the longest name the arguments start with.
+fn whisper_target<'a>(
+    rest: &'a str,
+    online: impl Iterator<Item = (u64, &'a str)>,
+) -> Option<(u64, &'a str, &'a str)> {
+    online
+        .filter_map(|(client_id, name)| {
+            let message = rest.strip_prefix(name)?;
+            (message.is_empty() || message.starts_with(' '))
+                .then(|| (client_id, name, message.trim()))
+        })
+        .max_by_key(|(_, name, _)| name.len())
+}
+
+/// A line from the server to a single player
+fn notice(network: &mut ServerNetwork, client_id: u64, text: &str) {
+    network.send(
+        client_id,
+        &ServerMessage::Chat {
+            scope: ChatScope::Server,
+            from: String::new(),
+            text: text.to_string(),
+        },
+    );
+}
+
+// === Components and Resources ===
+/// A chat message as received, before any checks
+#[derive(Event)]
+pub struct ChatReceived {
+    pub client_id: u64,
+    pub text: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whisper_picks_the_longest_matching_name() {
+        let online = [(1, "Bob"), (2, "Bob Smith"), (3, "Alice")];
+        assert_eq!(
+            whisper_target("Bob Smith hi there", online.into_iter()),
+            Some((2, "Bob Smith", "hi there"))
+        );
+        assert_eq!(
+            whisper_target("Bob hi", online.into_iter()),
+            Some((1, "Bob", "hi"))
+        );
+    }
+
+    #[test]
+    fn whisper_needs_a_whole_name() {
+        let online = [(1, "Bob"), (3, "Alice")];
+        assert_eq!(whisper_target("Bobby hi", online.into_iter()), None);
+        assert_eq!(whisper_target("Carol hi", online.into_iter()), None);
+        assert_eq!(
+            whisper_target("Alice", online.into_iter()),
+            Some((3, "Alice", ""))
+        );
+    }
+}
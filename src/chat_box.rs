@@ -0,0 +1,213 @@
+use crate::{
+    client::ClientNetwork,
+    protocol::{ChatScope, ClientMessage, MAX_CHAT_LEN},
+    screens::ClientState,
+};
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+use std::collections::VecDeque;
+
+/// Lines kept for scrolling back through
+const CHAT_HISTORY: usize = 100;
+
+/// Lines shown at once
+const CHAT_VISIBLE_LINES: usize = 8;
+
+const WHISPER_COLOR: Color = Color::srgb(0.85, 0.6, 1.0);
+const SERVER_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Chat>()
+        .add_systems(OnEnter(ClientState::Connecting), clear_chat)
+        .add_systems(OnEnter(ClientState::InGame), spawn_chat_box)
+        .add_systems(
+            Update,
+            (type_chat, update_chat_box)
+                .chain()
+                .run_if(in_state(ClientState::InGame)),
+        );
+}
+
+/// Run condition for gameplay input, which is ignored while typing
+pub fn typing(chat: Res<Chat>) -> bool {
+    chat.draft.is_some()
+}
+
+// === Chat ===
+fn clear_chat(mut chat: ResMut<Chat>) {
+    *chat = Chat::default();
+}
+
+/// Enter opens the input box (as does `/`, to start a command), Enter again sends and Escape
+/// cancels. Page Up and Page Down scroll the log while typing.
+pub fn type_chat(
+    mut keys: EventReader<KeyboardInput>,
+    mut chat: ResMut<Chat>,
+    mut network: ClientNetwork,
+) {
+    for event in keys.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        let chat = &mut *chat;
+
+        let Some(draft) = chat.draft.as_mut() else {
+            match &event.logical_key {
+                Key::Enter => chat.draft = Some(String::new()),
+                Key::Character(c) if c.as_str() == "/" => chat.draft = Some("/".to_string()),
+                _ => {}
+            }
+            continue;
+        };
+
+        match &event.logical_key {
+            Key::Enter => {
+                let text = draft.trim().to_string();
+                if !text.is_empty() {
+                    network.send(&ClientMessage::Chat { text });
+                }
+                chat.draft = None;
+                chat.scroll = 0;
+            }
+            Key::Escape => {
+                chat.draft = None;
+                chat.scroll = 0;
+            }
+            Key::Backspace => {
+                draft.pop();
+            }
+            Key::PageUp => {
+                let max_scroll = chat.lines.len().saturating_sub(CHAT_VISIBLE_LINES);
+                chat.scroll = (chat.scroll + CHAT_VISIBLE_LINES).min(max_scroll);
+            }
+            Key::PageDown => {
+                chat.scroll = chat.scroll.saturating_sub(CHAT_VISIBLE_LINES);
+            }
+            Key::Space if draft.chars().count() < MAX_CHAT_LEN => draft.push(' '),
+            Key::Character(c) => {
+                let room = MAX_CHAT_LEN.saturating_sub(draft.chars().count());
+                draft.extend(c.chars().filter(|c| !c.is_control()).take(room));
+            }
+            _ => {}
+        }
+    }
+}
+
+// === Chat Box ===
+fn spawn_chat_box(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(ClientState::InGame),
+        ChatPanel,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(40.0),
+            left: Val::Px(8.0),
+            width: Val::Px(420.0),
+            flex_direction: FlexDirection::Column,
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(Color::NONE),
+    ));
+}
+
+/// Rebuilds the visible lines, plus the input line while typing
+fn update_chat_box(
+    mut commands: Commands,
+    chat: Res<Chat>,
+    mut panel: Query<(Entity, &mut BackgroundColor), With<ChatPanel>>,
+) {
+    if !chat.is_changed() {
+        return;
+    }
+    let Ok((panel, mut background)) = panel.single_mut() else {
+        return;
+    };
+
+    *background = match chat.draft {
+        Some(_) => BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        None => BackgroundColor(Color::NONE),
+    };
+
+    let end = chat.lines.len() - chat.scroll.min(chat.lines.len());
+    let start = end.saturating_sub(CHAT_VISIBLE_LINES);
+    let mut entity = commands.entity(panel);
+    entity.despawn_related::<Children>();
+    entity.with_children(|parent| {
+        for line in chat.lines.range(start..end) {
+            parent.spawn(chat_text(line.display(), line.color()));
+        }
+        if let Some(draft) = &chat.draft {
+            parent.spawn(chat_text(format!("> {draft}_"), Color::WHITE));
+        }
+    });
+}
+
+fn chat_text(text: String, color: Color) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(color),
+    )
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+struct ChatPanel;
+
+pub struct ChatLine {
+    pub scope: ChatScope,
+    pub from: String,
+    pub text: String,
+}
+
+impl ChatLine {
+    fn display(&self) -> String {
+        match &self.scope {
+            ChatScope::All => format!("{}: {}", self.from, self.text),
+            ChatScope::Team(_) => format!("[Team] {}: {}", self.from, self.text),
+            ChatScope::Whisper { to } => format!("{} -> {to}: {}", self.from, self.text),
+            ChatScope::Server => self.text.clone(),
+        }
+    }
+
+    fn color(&self) -> Color {
+        match &self.scope {
+            ChatScope::All => Color::WHITE,
+            ChatScope::Team(team) => team.color(),
+            ChatScope::Whisper { .. } => WHISPER_COLOR,
+            ChatScope::Server => SERVER_COLOR,
+        }
+    }
+}
+
+/// Chat received this session, and what we're typing if the input box is open
+#[derive(Resource, Default)]
+pub struct Chat {
+    lines: VecDeque<ChatLine>,
+    draft: Option<String>,
+    /// Lines scrolled back from the newest
+    scroll: usize,
+}
+
+impl Chat {
+    pub fn push(&mut self, line: ChatLine) {
+        if self.lines.len() == CHAT_HISTORY {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+        // Keep the view still if the player has scrolled back
+        if self.scroll > 0 {
+            let max_scroll = self.lines.len().saturating_sub(CHAT_VISIBLE_LINES);
+            self.scroll = (self.scroll + 1).min(max_scroll);
+        }
+    }
+}
@@ -1,21 +1,52 @@
 #[cfg(feature = "dev")]
 use crate::dev_tools;
-use crate::{BoxCollectable, ClientMessage, PROTOCOL_ID, ServerMessage, connection_config};
-use bevy::color::palettes::css::{BLUE, YELLOW};
-use bevy::prelude::*;
+use crate::{
+    BoxCollectable, ClientMessage, MAX_ACCELERATION, PROTOCOL_ID, ServerChannel, ServerMessage,
+    arg_value, camera,
+    chat_box::{self, Chat, ChatLine, type_chat, typing},
+    connection_config,
+    controls::{self, Controls, PlayerInput},
+    cooldowns::{self, AbilityCooldowns},
+    effects::{self, PlayerEffects},
+    hud::{self, MatchHud, RoundResults},
+    inspect::Inspector,
+    minimap,
+    powerups::SPEED_BOOST_MULTIPLIER,
+    prediction::{self, NetMotion},
+    protocol::{
+        COLLECT_RADIUS, ConnectData, DRONE_RADIUS, INPUT_INTERVAL_SECS, MAX_NAME_LEN,
+        PLAYER_COLORS, PlayerProfile, PowerUp, Team, carry_multiplier,
+    },
+    recording::Route,
+    scoreboard::{self, Scoreboard},
+    screens::{self, ClientState, LastDisconnect},
+    shooting::{self, RemoteProjectile, Stun, hit_flash, projectile},
+    spectator_camera::{self, spectating},
+};
+use bevy::ecs::system::SystemParam;
+use bevy::{platform::collections::HashMap, prelude::*};
 
 use bevy_renet2::{
     netcode::{ClientAuthentication, NetcodeClientPlugin, NetcodeClientTransport},
     prelude::{RenetClient, RenetClientPlugin, client_connected},
 };
-use renet2_netcode::NativeSocket;
+use renet2_netcode::{NETCODE_USER_DATA_BYTES, NativeSocket};
 use std::{
-    net::UdpSocket,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
     time::{SystemTime, UNIX_EPOCH},
 };
 
-pub fn run() {
-    let (client, transport) = new_client();
+const DEFAULT_SERVER_ADDR: &str = "127.0.0.1:5000";
+
+const DRONE_COLOR: Color = Color::srgb(0.55, 0.55, 0.6);
+
+/// Seconds to wait for a box we asked for to go before asking again
+const COLLECT_RETRY_SECS: f32 = 1.0;
+
+/// Seconds to wait for the server before giving up on a connection attempt
+const CONNECT_TIMEOUT_SECS: f32 = 10.0;
+
+pub fn run(args: &[String]) {
     App::new()
         .add_plugins(DefaultPlugins)
         .add_plugins(
@@ -24,13 +55,48 @@ pub fn run() {
         )
         .add_plugins(NetcodeClientPlugin)
         .add_plugins(RenetClientPlugin)
-        .insert_resource(client)
-        .insert_resource(transport)
+        .add_plugins(screens::plugin)
+        .add_plugins(scoreboard::plugin)
+        .add_plugins(hud::plugin)
+        .add_plugins(effects::plugin)
+        .add_plugins(prediction::plugin)
+        .add_plugins(cooldowns::plugin)
+        .add_plugins(chat_box::plugin)
+        .add_plugins(shooting::plugin)
+        .add_plugins(camera::plugin)
+        .add_plugins(minimap::plugin)
+        .add_plugins(spectator_camera::plugin)
+        .add_plugins(controls::plugin)
+        .insert_resource(ClientSettings::from_args(args))
+        .insert_resource(Controls::from_args(args))
         .insert_resource(ClientInfo::default())
+        .insert_resource(Inspector::from_args(args))
         .configure_sets(Update, Connected.run_if(client_connected))
-        .add_systems(Startup, setup_player)
-        .add_systems(Update, move_player)
-        .add_systems(Update, (receive_messages, check_collectibles))
+        .add_systems(Startup, setup_camera)
+        .add_systems(OnEnter(ClientState::Connecting), start_connecting)
+        .add_systems(
+            Update,
+            wait_for_connection.run_if(in_state(ClientState::Connecting)),
+        )
+        .add_systems(
+            OnEnter(ClientState::InGame),
+            setup_player.run_if(not(spectating)),
+        )
+        .add_systems(
+            Update,
+            (
+                move_player.run_if(not(typing).and(not(spectating))),
+                receive_messages,
+                update_name_labels.run_if(resource_changed::<Scoreboard>),
+                check_collectibles,
+                detect_disconnect,
+                // Escape closes the chat box before it leaves the game
+                leave_game.run_if(not(typing)).before(type_chat),
+            )
+                .run_if(in_state(ClientState::InGame)),
+        )
+        .add_systems(OnEnter(ClientState::MainMenu), close_connection)
+        .add_systems(OnEnter(ClientState::Disconnected), close_connection)
         // .add_systems(
         //     PostUpdate,
         //     player_physics_simulation.in_set(PhysicsSet::Writeback),
@@ -38,8 +104,11 @@ pub fn run() {
         .run();
 }
 
-fn new_client() -> (RenetClient, NetcodeClientTransport) {
-    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+fn new_client(
+    server_addr: SocketAddr,
+    user_data: [u8; NETCODE_USER_DATA_BYTES],
+) -> (RenetClient, NetcodeClientTransport) {
+    let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
     let native_socket = NativeSocket::new(socket).unwrap();
     let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
     let client_id = now.as_millis() as u64;
@@ -47,9 +116,9 @@ fn new_client() -> (RenetClient, NetcodeClientTransport) {
     let auth = ClientAuthentication::Unsecure {
         client_id,
         protocol_id: PROTOCOL_ID,
-        server_addr: "127.0.0.1:5000".parse().unwrap(),
+        server_addr,
         socket_id: 0,
-        user_data: None,
+        user_data: Some(user_data),
     };
 
     let transport = NetcodeClientTransport::new(now, auth, native_socket).unwrap();
@@ -57,109 +126,318 @@ fn new_client() -> (RenetClient, NetcodeClientTransport) {
     (client, transport)
 }
 
+// === Connection Lifecycle ===
+fn start_connecting(
+    mut commands: Commands,
+    settings: Res<ClientSettings>,
+    mut client_info: ResMut<ClientInfo>,
+    mut last_disconnect: ResMut<LastDisconnect>,
+    mut next_state: ResMut<NextState<ClientState>>,
+) {
+    let Some(server_addr) = settings
+        .server_addr
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+    else {
+        last_disconnect.0 = format!("Could not resolve '{}'", settings.server_addr);
+        next_state.set(ClientState::Disconnected);
+        return;
+    };
+
+    // A session token from a previous connection lets the server hand back our old player
+    let connect_data = ConnectData {
+        resume_token: client_info.session_token.filter(|_| !settings.spectate),
+        profile: PlayerProfile {
+            name: settings.name.clone(),
+            color: settings.color,
+            team: settings.team,
+        },
+        spectate: settings.spectate,
+    };
+    let user_data = match connect_data.to_user_data() {
+        Ok(user_data) => user_data,
+        Err(e) => {
+            last_disconnect.0 = format!("Could not send our name to the server: {e}");
+            next_state.set(ClientState::Disconnected);
+            return;
+        }
+    };
+    client_info.id = None;
+    client_info.snapshot_tick = 0;
+    client_info.kick_reason = None;
+
+    let (client, transport) = new_client(server_addr, user_data);
+    commands.insert_resource(client);
+    commands.insert_resource(transport);
+    commands.insert_resource(ConnectTimeout(Timer::from_seconds(
+        CONNECT_TIMEOUT_SECS,
+        TimerMode::Once,
+    )));
+}
+
+fn wait_for_connection(
+    time: Res<Time>,
+    client: Res<RenetClient>,
+    mut timeout: ResMut<ConnectTimeout>,
+    mut last_disconnect: ResMut<LastDisconnect>,
+    mut next_state: ResMut<NextState<ClientState>>,
+) {
+    if client.is_connected() {
+        next_state.set(ClientState::InGame);
+    } else if let Some(reason) = client.disconnect_reason() {
+        last_disconnect.0 = format!("Could not connect: {reason:?}");
+        next_state.set(ClientState::Disconnected);
+    } else if timeout.0.tick(time.delta()).finished() {
+        last_disconnect.0 = "Timed out waiting for the server".to_string();
+        next_state.set(ClientState::Disconnected);
+    }
+}
+
+fn detect_disconnect(
+    client: Res<RenetClient>,
+    client_info: Res<ClientInfo>,
+    mut last_disconnect: ResMut<LastDisconnect>,
+    mut next_state: ResMut<NextState<ClientState>>,
+) {
+    if let Some(reason) = client.disconnect_reason() {
+        last_disconnect.0 = match &client_info.kick_reason {
+            Some(kick_reason) => format!("Kicked: {kick_reason}"),
+            None => format!("Lost connection: {reason:?}"),
+        };
+        next_state.set(ClientState::Disconnected);
+    }
+}
+
+fn leave_game(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut last_disconnect: ResMut<LastDisconnect>,
+    mut next_state: ResMut<NextState<ClientState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        last_disconnect.0 = "You left the game".to_string();
+        next_state.set(ClientState::Disconnected);
+    }
+}
+
+fn close_connection(mut commands: Commands, transport: Option<ResMut<NetcodeClientTransport>>) {
+    if let Some(mut transport) = transport {
+        transport.disconnect();
+    }
+    commands.remove_resource::<RenetClient>();
+    commands.remove_resource::<NetcodeClientTransport>();
+    commands.remove_resource::<ConnectTimeout>();
+}
+
 #[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Connected;
 
-fn setup_player(mut commands: Commands) {
-    commands.spawn(Camera2d::default());
+/// Wraps the renet client so every message is encoded and inspected in one place
+#[derive(SystemParam)]
+pub struct ClientNetwork<'w> {
+    client: ResMut<'w, RenetClient>,
+    transport: Res<'w, NetcodeClientTransport>,
+    inspector: Res<'w, Inspector>,
+}
 
+impl ClientNetwork<'_> {
+    pub fn send(&mut self, message: &ClientMessage) {
+        let bytes = bincode::serde::encode_to_vec(message, bincode::config::standard()).unwrap();
+        let channel = message.channel();
+        self.inspector.inspect(
+            None,
+            Route::FromClient {
+                client_id: self.transport.client_id(),
+            },
+            channel.into(),
+            &bytes,
+        );
+        self.client.send_message(channel, bytes);
+    }
+
+    /// Returns the next message from the server that decodes, logging any that don't
+    pub fn receive(&mut self) -> Option<ServerMessage> {
+        while let Some(bytes) = self.client.receive_message(ServerChannel::World) {
+            self.inspector.inspect(
+                None,
+                Route::ToClient {
+                    client_id: self.transport.client_id(),
+                },
+                ServerChannel::World.into(),
+                &bytes,
+            );
+
+            let Ok((message, _)) = bincode::serde::decode_from_slice::<ServerMessage, _>(
+                &bytes,
+                bincode::config::standard(),
+            ) else {
+                error!("Failed to decode server message");
+                continue;
+            };
+
+            return Some(message);
+        }
+        None
+    }
+}
+
+fn setup_camera(mut commands: Commands) {
+    commands.spawn(Camera2d);
+}
+
+fn setup_player(mut commands: Commands, settings: Res<ClientSettings>) {
     commands.spawn((
+        StateScoped(ClientState::InGame),
         Player,
+        NetMotion::default(),
         Transform::from_xyz(0.0, 0.0, 0.0),
         Sprite {
-            color: BLUE.into(),
+            color: PLAYER_COLORS[settings.color as usize],
             custom_size: Some(Vec2::splat(30.0)),
             ..default()
         },
     ));
 }
 
-fn move_player(keys: Res<ButtonInput<KeyCode>>, time: Res<Time>, mut client: ResMut<RenetClient>) {
-    let mut direction = Vec2::ZERO;
-    if keys.pressed(KeyCode::KeyW) {
-        direction.y += 1.0;
-    }
-    if keys.pressed(KeyCode::KeyS) {
-        direction.y -= 1.0;
-    }
-    if keys.pressed(KeyCode::KeyA) {
-        direction.x -= 1.0;
-    }
-    if keys.pressed(KeyCode::KeyD) {
-        direction.x += 1.0;
+#[allow(
+    clippy::too_many_arguments,
+    reason = "prediction needs effects, carried weight and stun alongside the input"
+)]
+fn move_player(
+    input: Res<PlayerInput>,
+    time: Res<Time>,
+    client_info: Res<ClientInfo>,
+    player_effects: Res<PlayerEffects>,
+    match_hud: Res<MatchHud>,
+    stun: Res<Stun>,
+    mut local_player: Query<&mut NetMotion, (With<Player>, Without<RemotePlayer>)>,
+    mut network: ClientNetwork,
+    // Latest direction, and seconds of it not sent yet
+    mut unsent: Local<(Vec2, f32)>,
+) {
+    if stun.active() {
+        return;
     }
 
-    if direction != Vec2::ZERO {
-        let dir = direction.normalize_or_zero();
+    // Not normalized: a half-pushed stick asks for half the acceleration
+    let dir = input.movement;
+    if dir != Vec2::ZERO {
         let delta = time.delta_secs();
 
-        let msg = ClientMessage::MoveInput {
-            direction: dir,
+        // Predict the push the server is about to apply so input feels immediate
+        let boosted = client_info
+            .id
+            .is_some_and(|id| player_effects.has(id, PowerUp::SpeedBoost));
+        let boost = if boosted { SPEED_BOOST_MULTIPLIER } else { 1.0 };
+        let multiplier = boost * carry_multiplier(match_hud.carried_weight());
+        if let Ok(mut motion) = local_player.single_mut() {
+            motion.velocity += dir * MAX_ACCELERATION * multiplier * delta;
+        }
+
+        *unsent = (dir, unsent.1 + delta);
+    }
+
+    // Input goes out in fixed slices of time rather than once a frame, so a fast monitor
+    // doesn't send more messages, and never more than one slice for each slice of time
+    // passed, which is all the server allows. Letting go drops the part of a slice left over.
+    while unsent.1 >= INPUT_INTERVAL_SECS {
+        unsent.1 -= INPUT_INTERVAL_SECS;
+        network.send(&ClientMessage::MoveInput {
+            direction: unsent.0,
             frame: 0,
-            delta,
-        };
-        let bytes = bincode::serde::encode_to_vec(&msg, bincode::config::standard()).unwrap();
-        client.send_message(0, bytes);
-        // }
+            delta: INPUT_INTERVAL_SECS,
+        });
+    }
+    if dir == Vec2::ZERO {
+        unsent.1 = 0.0;
     }
 }
 
+#[allow(
+    clippy::too_many_arguments,
+    clippy::type_complexity,
+    reason = "applies every kind of server message"
+)]
 fn receive_messages(
     mut commands: Commands,
-    mut client: ResMut<RenetClient>,
+    mut network: ClientNetwork,
     mut client_info: ResMut<ClientInfo>,
-    mut players: Query<(Entity, &mut Transform, Option<&RemotePlayer>), With<Player>>,
-    collectible_query: Query<(Entity, &RemoteCollectibleId)>,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut match_hud: ResMut<MatchHud>,
+    mut player_effects: ResMut<PlayerEffects>,
+    mut ability_cooldowns: ResMut<AbilityCooldowns>,
+    mut stun: ResMut<Stun>,
+    mut chat: ResMut<Chat>,
+    mut players: Query<
+        (
+            Entity,
+            &mut Transform,
+            Option<&RemotePlayer>,
+            &mut NetMotion,
+        ),
+        With<Player>,
+    >,
+    mut local_player: Query<
+        (Entity, Option<&PlayerProfile>, &mut Sprite),
+        (With<Player>, Without<RemotePlayer>),
+    >,
+    mut collectible_query: Query<(Entity, &RemoteCollectibleId, &mut Transform), Without<Player>>,
+    map_tiles: Query<Entity, With<MapTile>>,
+    projectiles: Query<(Entity, &RemoteProjectile)>,
+    mut drones: Query<(&RemoteDrone, &mut NetMotion), Without<Player>>,
 ) {
-    while let Some(bytes) = client.receive_message(0) {
-        let Ok((message, _)) = bincode::serde::decode_from_slice::<ServerMessage, _>(
-            &bytes,
-            bincode::config::standard(),
-        ) else {
-            error!("Failed to decode server message");
-            continue;
-        };
-
+    while let Some(message) = network.receive() {
         match message {
             ServerMessage::AssignClientId { client_id } => {
                 info!("Received client ID: {client_id}");
                 client_info.id = Some(client_id);
             }
 
-            ServerMessage::PlayerPositions(player_positions) => {
-                for data in player_positions {
-                    for (_ent, mut transform, remote) in players.iter_mut() {
-                        if Some(data.client_id) == client_info.id {
-                            // This is us
-                            transform.translation = data.position;
-                            transform.rotation = data.rotation;
-                            break;
-                        }
+            ServerMessage::SessionToken { token } => {
+                client_info.session_token = Some(token);
+            }
 
-                        match remote {
-                            Some(remote_player) => {
-                                if remote_player.client_id == data.client_id {
-                                    transform.translation = data.position;
-                                    transform.rotation = data.rotation;
+            ServerMessage::PlayerPositions {
+                tick,
+                players: positions,
+            } => {
+                client_info.snapshot_tick = tick;
+                for data in positions {
+                    let is_us = Some(data.client_id) == client_info.id;
+                    let player = players.iter_mut().find(|(_, _, remote, _)| match remote {
+                        Some(remote_player) => remote_player.client_id == data.client_id,
+                        None => is_us,
+                    });
 
-                                    break;
-                                }
-                            }
-                            None => {}
-                        }
+                    if let Some((_, mut transform, _, mut motion)) = player {
+                        transform.rotation = data.rotation;
+                        motion.correct(data.position, data.velocity);
                     }
                 }
             }
 
+            ServerMessage::MapData(map) => {
+                info!("Playing on map {}", map.name);
+                for entity in map_tiles.iter() {
+                    commands.entity(entity).despawn();
+                }
+                for sprite in map.sprites() {
+                    commands.spawn((StateScoped(ClientState::InGame), MapTile, sprite));
+                }
+                // Kept for the camera, which stays within the map
+                commands.insert_resource(map);
+            }
+
             ServerMessage::SpawnCollectibles(collectibles) => {
                 for info in collectibles {
                     commands.spawn((
+                        StateScoped(ClientState::InGame),
                         BoxCollectable,
                         RemoteCollectibleId(info.id),
                         Transform::from_translation(info.position),
                         Sprite {
-                            color: YELLOW.into(),
-                            custom_size: Some(Vec2::splat(20.0)),
+                            color: info.kind.color(),
+                            custom_size: Some(Vec2::splat(info.kind.size())),
                             ..default()
                         },
                     ));
@@ -167,19 +445,123 @@ fn receive_messages(
             }
 
             ServerMessage::DespawnCollectible { id } => {
-                for (entity, box_id) in collectible_query.iter() {
+                for (entity, box_id, _) in collectible_query.iter() {
                     if box_id.0 == id {
                         commands.entity(entity).despawn();
                     }
                 }
             }
 
-            ServerMessage::SpawnRemotePlayer { client_id } => {
+            ServerMessage::CollectiblePositions(positions) => {
+                for moved in positions {
+                    for (_, box_id, mut transform) in collectible_query.iter_mut() {
+                        if box_id.0 == moved.id {
+                            transform.translation = moved.position;
+                        }
+                    }
+                }
+            }
+
+            ServerMessage::EffectStarted {
+                client_id,
+                power_up,
+                remaining_secs,
+            } => {
+                player_effects.start(client_id, power_up, remaining_secs);
+            }
+
+            ServerMessage::EffectEnded {
+                client_id,
+                power_up,
+            } => {
+                player_effects.end(client_id, power_up);
+            }
+
+            ServerMessage::AbilityCooldown {
+                ability,
+                remaining_secs,
+            } => {
+                ability_cooldowns.set(ability, remaining_secs);
+            }
+
+            ServerMessage::SpawnDrones(spawned) => {
+                for data in spawned {
+                    if drones.iter().any(|(drone, _)| drone.0 == data.id) {
+                        continue;
+                    }
+
+                    let mut motion = NetMotion::default();
+                    motion.correct(data.position, data.velocity);
+                    commands.spawn((
+                        StateScoped(ClientState::InGame),
+                        RemoteDrone(data.id),
+                        motion,
+                        Transform::from_translation(data.position),
+                        Sprite {
+                            color: DRONE_COLOR,
+                            custom_size: Some(Vec2::splat(DRONE_RADIUS * 2.0)),
+                            ..default()
+                        },
+                    ));
+                }
+            }
+
+            ServerMessage::DronePositions(positions) => {
+                for data in positions {
+                    if let Some((_, mut motion)) =
+                        drones.iter_mut().find(|(drone, _)| drone.0 == data.id)
+                    {
+                        motion.correct(data.position, data.velocity);
+                    }
+                }
+            }
+
+            ServerMessage::ProjectileSpawned(info) => {
+                commands.spawn(projectile(&info));
+            }
+
+            ServerMessage::ProjectileDespawned { id } => {
+                for (entity, projectile) in projectiles.iter() {
+                    if projectile.id == id {
+                        commands.entity(entity).despawn();
+                    }
+                }
+            }
+
+            ServerMessage::PlayerHit {
+                target, stun_secs, ..
+            } => {
+                let is_us = Some(target) == client_info.id;
+                if is_us {
+                    stun.0 = stun_secs;
+                }
+
+                let player = players.iter().find(|(_, _, remote, _)| match remote {
+                    Some(remote_player) => remote_player.client_id == target,
+                    None => is_us,
+                });
+                if let Some((entity, ..)) = player {
+                    commands.entity(entity).with_child(hit_flash());
+                }
+            }
+
+            ServerMessage::SpawnRemotePlayer { client_id, profile } => {
+                scoreboard.set_name(client_id, &profile.name);
+                scoreboard.set_team(client_id, profile.team);
+
                 if Some(client_id) == client_info.id {
-                    return;
+                    // This is us; pick up the name and color the server settled on
+                    if let Ok((entity, None, mut sprite)) = local_player.single_mut() {
+                        sprite.color = profile.color();
+                        commands
+                            .entity(entity)
+                            .insert(profile.clone())
+                            .with_child(name_label(&profile.name));
+                    }
+                    continue;
                 }
                 let mut found = false;
-                for (_, _, remote_player) in players.iter() {
+                for (_, _, remote_player, _) in players.iter() {
                     match remote_player {
                         Some(player) => {
                             if player.client_id == client_id {
@@ -191,22 +573,80 @@ fn receive_messages(
                 }
                 if !found {
                     commands.spawn((
+                        StateScoped(ClientState::InGame),
                         Transform::default(),
                         Sprite {
-                            color: Color::srgb(0.8, 0.2, 1.0),
+                            color: profile.color(),
                             custom_size: Some(Vec2::splat(30.0)),
                             ..default()
                         },
                         Player,
+                        NetMotion::default(),
                         RemotePlayer {
                             client_id: client_id,
                         },
+                        children![name_label(&profile.name)],
+                        profile,
                     ));
                 }
             }
 
+            ServerMessage::ScoreUpdate { client_id, score } => {
+                scoreboard.set_score(client_id, score);
+            }
+
+            ServerMessage::MatchState {
+                phase,
+                round,
+                remaining_secs,
+            } => {
+                match_hud.phase = Some(phase);
+                match_hud.round = round;
+                match_hud.remaining_secs = remaining_secs;
+            }
+
+            ServerMessage::RoundResults {
+                round,
+                winner,
+                winning_team,
+                standings,
+            } => {
+                match_hud.results = Some(RoundResults {
+                    round,
+                    winner,
+                    winning_team,
+                    standings,
+                });
+            }
+
+            ServerMessage::TeamScore { team, score } => {
+                match_hud.set_team_score(team, score);
+            }
+
+            ServerMessage::Chat { scope, from, text } => {
+                chat.push(ChatLine { scope, from, text });
+            }
+
+            ServerMessage::PlayerRenamed { client_id, name } => {
+                scoreboard.set_name(client_id, &name);
+            }
+
+            ServerMessage::Kicked { reason } => {
+                warn!("Kicked by the server: {reason}");
+                client_info.kick_reason = Some(reason);
+            }
+
+            ServerMessage::InventoryUpdate { client_id, items } => {
+                if Some(client_id) == client_info.id {
+                    match_hud.carrying = items;
+                }
+            }
+
             ServerMessage::DespawnPlayer { client_id } => {
-                for (entity, _, remote_player) in players.iter() {
+                scoreboard.remove(client_id);
+                player_effects.0.remove(&client_id);
+
+                for (entity, _, remote_player, _) in players.iter() {
                     if let Some(player) = remote_player {
                         if player.client_id == client_id {
                             commands.entity(entity).despawn();
@@ -218,11 +658,58 @@ fn receive_messages(
     }
 }
 
+/// Keeps the labels above players in step with the names on the scoreboard, which change
+/// when someone uses `/name`
+fn update_name_labels(
+    client_info: Res<ClientInfo>,
+    scoreboard: Res<Scoreboard>,
+    players: Query<(Option<&RemotePlayer>, &Children), With<Player>>,
+    mut labels: Query<&mut Text2d>,
+) {
+    for (remote_player, children) in players.iter() {
+        let client_id = remote_player
+            .map(|player| player.client_id)
+            .or(client_info.id);
+        let Some(entry) = client_id.and_then(|id| scoreboard.entries.get(&id)) else {
+            continue;
+        };
+
+        for child in children.iter() {
+            let Ok(mut label) = labels.get_mut(child) else {
+                continue;
+            };
+            if label.0 != entry.name {
+                label.0 = entry.name.clone();
+            }
+        }
+    }
+}
+
+/// Name shown above a player's square
+pub fn name_label(name: &str) -> impl Bundle {
+    (
+        Text2d::new(name),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        Transform::from_xyz(0.0, 26.0, 1.0),
+    )
+}
+
+/// Asks for each box we touch once, and again only if it's still there a while later, since
+/// the server may refuse, e.g. with a full inventory or between rounds
 fn check_collectibles(
+    time: Res<Time>,
     player_query: Query<&Transform, (With<Player>, Without<RemotePlayer>)>,
     boxes: Query<(&Transform, &RemoteCollectibleId)>,
-    mut client: ResMut<RenetClient>,
+    mut network: ClientNetwork,
+    // When we last asked for each box
+    mut requested: Local<HashMap<u64, f32>>,
 ) {
+    let now = time.elapsed_secs();
+    requested.retain(|_, asked_at| now - *asked_at < COLLECT_RETRY_SECS);
+
     let Ok(player_transform) = player_query.single() else {
         return;
     };
@@ -231,10 +718,9 @@ fn check_collectibles(
         let distance = player_transform
             .translation
             .distance(box_transform.translation);
-        if distance < 40.0 {
-            let msg = ClientMessage::AttemptCollect { id: box_id.0 };
-            let bytes = bincode::serde::encode_to_vec(&msg, bincode::config::standard()).unwrap();
-            client.send_message(0, bytes);
+        if distance < COLLECT_RADIUS && !requested.contains_key(&box_id.0) {
+            requested.insert(box_id.0, now);
+            network.send(&ClientMessage::AttemptCollect { id: box_id.0 });
         }
     }
 }
@@ -252,7 +738,71 @@ pub struct RemotePlayer {
 #[derive(Component)]
 pub struct RemoteCollectibleId(pub u64);
 
+/// Walls, obstacles and floor of the current map
+#[derive(Component)]
+pub struct MapTile;
+
+/// A server-controlled drone, by network ID
+#[derive(Component)]
+pub struct RemoteDrone(pub u64);
+
 #[derive(Resource, Default)]
 pub struct ClientInfo {
     pub id: Option<u64>,
+    /// Presented on reconnect so the server hands back the same player
+    pub session_token: Option<u64>,
+    /// Tick of the newest player positions, sent back with shots for lag compensation
+    pub snapshot_tick: u64,
+    /// Why the server said it was about to disconnect us, if it did
+    pub kick_reason: Option<String>,
 }
+
+/// Connection details entered on the main menu, prefilled from `--connect`, `--name` and
+/// `--color`
+#[derive(Resource)]
+pub struct ClientSettings {
+    pub server_addr: String,
+    pub name: String,
+    /// Index into [`PLAYER_COLORS`]
+    pub color: u8,
+    /// Team to ask for in team mode, or `None` to be balanced automatically
+    pub team: Option<Team>,
+    /// Join without a player, just to watch
+    pub spectate: bool,
+}
+
+impl ClientSettings {
+    pub fn from_args(args: &[String]) -> Self {
+        Self {
+            server_addr: arg_value(args, "--connect")
+                .unwrap_or(DEFAULT_SERVER_ADDR)
+                .to_string(),
+            // Same limits as the name field, so the server accepts it and it fits in the
+            // connect token
+            name: arg_value(args, "--name")
+                .unwrap_or("Player")
+                .chars()
+                .filter(|c| !c.is_control())
+                .take(MAX_NAME_LEN)
+                .collect(),
+            color: arg_value(args, "--color")
+                .and_then(|color| color.parse().ok())
+                .unwrap_or(0)
+                % PLAYER_COLORS.len() as u8,
+            team: match arg_value(args, "--team") {
+                Some("red") => Some(Team::Red),
+                Some("blue") => Some(Team::Blue),
+                _ => None,
+            },
+            spectate: false,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        !self.server_addr.trim().is_empty() && !self.name.trim().is_empty()
+    }
+}
+
+/// Gives up on a connection attempt once it runs out
+#[derive(Resource)]
+pub struct ConnectTimeout(pub Timer);
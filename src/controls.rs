@@ -0,0 +1,334 @@
+use crate::{arg_value, protocol::Ability};
+use bevy::{input::InputSystem, platform::collections::HashSet, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+const DEFAULT_SETTINGS_FILE: &str = "settings.ron";
+
+/// How far a stick has to be pushed before it counts, from 0 to 1
+const DEFAULT_STICK_DEAD_ZONE: f32 = 0.15;
+
+/// Keys with fixed uses that can't be bound to an action: chat, the scoreboard, the minimap,
+/// zoom and following players while spectating
+pub const RESERVED_KEYS: [KeyCode; 9] = [
+    KeyCode::Enter,
+    KeyCode::NumpadEnter,
+    KeyCode::Slash,
+    KeyCode::Tab,
+    KeyCode::KeyM,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::KeyQ,
+    KeyCode::KeyE,
+];
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PlayerInput>()
+        .add_systems(PreUpdate, read_player_input.after(InputSystem));
+}
+
+// === Input ===
+/// Turns the keyboard, mouse and gamepads into actions and a movement vector, so gameplay
+/// doesn't care which device or keys are in use
+fn read_player_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    controls: Res<Controls>,
+    mut input: ResMut<PlayerInput>,
+) {
+    input.pressed.clear();
+    input.just_pressed.clear();
+
+    for (action, bindings) in &controls.settings.bindings {
+        for binding in bindings {
+            let (pressed, just_pressed) = match binding {
+                Binding::Key(key) => (keys.pressed(*key), keys.just_pressed(*key)),
+                Binding::Mouse(button) => (mouse.pressed(*button), mouse.just_pressed(*button)),
+                Binding::Gamepad(button) => (
+                    gamepads.iter().any(|gamepad| gamepad.pressed(*button)),
+                    gamepads.iter().any(|gamepad| gamepad.just_pressed(*button)),
+                ),
+            };
+            if pressed {
+                input.pressed.insert(*action);
+            }
+            if just_pressed {
+                input.just_pressed.insert(*action);
+            }
+        }
+    }
+
+    let axis = |negative: Action, positive: Action| {
+        input.pressed(positive) as i8 as f32 - input.pressed(negative) as i8 as f32
+    };
+    let digital = Vec2::new(
+        axis(Action::MoveLeft, Action::MoveRight),
+        axis(Action::MoveDown, Action::MoveUp),
+    );
+
+    let dead_zone = controls.settings.stick_dead_zone;
+    let left_stick = gamepads
+        .iter()
+        .map(|gamepad| apply_dead_zone(gamepad.left_stick(), dead_zone))
+        .find(|stick| *stick != Vec2::ZERO);
+    let right_stick = gamepads
+        .iter()
+        .map(|gamepad| apply_dead_zone(gamepad.right_stick(), dead_zone))
+        .find(|stick| *stick != Vec2::ZERO);
+
+    // Keys give full speed in any direction; a stick keeps how far it's pushed
+    input.movement = match left_stick {
+        Some(stick) if digital == Vec2::ZERO => stick,
+        _ => digital.normalize_or_zero(),
+    };
+    input.aim = right_stick.map(Vec2::normalize);
+}
+
+/// Zero inside the dead zone, then rescaled so the edge of the dead zone starts from zero
+fn apply_dead_zone(stick: Vec2, dead_zone: f32) -> Vec2 {
+    let length = stick.length();
+    if length <= dead_zone {
+        return Vec2::ZERO;
+    }
+    let scaled = ((length - dead_zone) / (1.0 - dead_zone).max(f32::EPSILON)).min(1.0);
+    stick / length * scaled
+}
+
+// === Components and Resources ===
+/// Something the player can bind an input to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Ability(Ability),
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Fire,
+        Action::Ability(Ability::Dash),
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Fire => "Fire",
+            Action::Ability(Ability::Dash) => "Dash",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    pub fn is_gamepad(&self) -> bool {
+        matches!(self, Binding::Gamepad(_))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                ["Key", "Digit", "Arrow"]
+                    .iter()
+                    .find_map(|prefix| name.strip_prefix(prefix))
+                    .unwrap_or(&name)
+                    .to_string()
+            }
+            Binding::Mouse(button) => format!("{button:?} click"),
+            Binding::Gamepad(button) => format!("Pad {button:?}"),
+        }
+    }
+}
+
+/// What's saved to the settings file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct InputSettings {
+    pub bindings: BTreeMap<Action, Vec<Binding>>,
+    pub stick_dead_zone: f32,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        use Binding::*;
+
+        let bindings = [
+            (
+                Action::MoveUp,
+                vec![
+                    Key(KeyCode::KeyW),
+                    Key(KeyCode::ArrowUp),
+                    Gamepad(GamepadButton::DPadUp),
+                ],
+            ),
+            (
+                Action::MoveDown,
+                vec![
+                    Key(KeyCode::KeyS),
+                    Key(KeyCode::ArrowDown),
+                    Gamepad(GamepadButton::DPadDown),
+                ],
+            ),
+            (
+                Action::MoveLeft,
+                vec![
+                    Key(KeyCode::KeyA),
+                    Key(KeyCode::ArrowLeft),
+                    Gamepad(GamepadButton::DPadLeft),
+                ],
+            ),
+            (
+                Action::MoveRight,
+                vec![
+                    Key(KeyCode::KeyD),
+                    Key(KeyCode::ArrowRight),
+                    Gamepad(GamepadButton::DPadRight),
+                ],
+            ),
+            (
+                Action::Fire,
+                vec![
+                    Mouse(MouseButton::Left),
+                    Gamepad(GamepadButton::RightTrigger2),
+                ],
+            ),
+            (
+                Action::Ability(Ability::Dash),
+                vec![Key(KeyCode::Space), Gamepad(GamepadButton::South)],
+            ),
+        ];
+
+        Self {
+            bindings: bindings.into_iter().collect(),
+            stick_dead_zone: DEFAULT_STICK_DEAD_ZONE,
+        }
+    }
+}
+
+/// Input bindings, loaded from and saved to the settings file given with `--settings`
+#[derive(Resource)]
+pub struct Controls {
+    path: PathBuf,
+    pub settings: InputSettings,
+}
+
+impl Controls {
+    pub fn from_args(args: &[String]) -> Self {
+        let path = PathBuf::from(arg_value(args, "--settings").unwrap_or(DEFAULT_SETTINGS_FILE));
+        let settings = match fs::read_to_string(&path) {
+            Ok(text) => match ron::from_str::<InputSettings>(&text) {
+                Ok(mut settings) => {
+                    // Actions added since the file was written start out with their defaults
+                    for (action, bindings) in InputSettings::default().bindings {
+                        settings.bindings.entry(action).or_insert(bindings);
+                    }
+                    info!("Loaded controls from {}", path.display());
+                    settings
+                }
+                Err(e) => {
+                    warn!("Ignoring controls in {}: {e}", path.display());
+                    InputSettings::default()
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => InputSettings::default(),
+            Err(e) => {
+                warn!("Failed to read {}: {e}", path.display());
+                InputSettings::default()
+            }
+        };
+
+        Self { path, settings }
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.settings
+            .bindings
+            .get(&action)
+            .map_or(&[], |bindings| bindings.as_slice())
+    }
+
+    /// Keyboard and mouse bindings, e.g. `W / Up`
+    pub fn describe(&self, action: Action) -> String {
+        let labels: Vec<String> = self
+            .bindings(action)
+            .iter()
+            .filter(|binding| !binding.is_gamepad())
+            .map(Binding::label)
+            .collect();
+        if labels.is_empty() {
+            "unbound".to_string()
+        } else {
+            labels.join(" / ")
+        }
+    }
+
+    /// Makes `binding` the only one of its kind (keyboard and mouse, or gamepad) for `action`,
+    /// taking it away from any other action, and saves the result
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        for bindings in self.settings.bindings.values_mut() {
+            bindings.retain(|other| *other != binding);
+        }
+        let bindings = self.settings.bindings.entry(action).or_default();
+        bindings.retain(|other| other.is_gamepad() != binding.is_gamepad());
+        bindings.insert(0, binding);
+        self.save();
+    }
+
+    pub fn reset(&mut self) {
+        self.settings = InputSettings::default();
+        self.save();
+    }
+
+    fn save(&self) {
+        let text = match ron::ser::to_string_pretty(&self.settings, default()) {
+            Ok(text) => text,
+            Err(e) => {
+                error!("Failed to encode controls: {e}");
+                return;
+            }
+        };
+        match fs::write(&self.path, text) {
+            Ok(()) => info!("Saved controls to {}", self.path.display()),
+            Err(e) => error!("Failed to save controls to {}: {e}", self.path.display()),
+        }
+    }
+}
+
+/// This frame's input, read through the bindings in [`Controls`]
+#[derive(Resource, Default)]
+pub struct PlayerInput {
+    /// Where the player wants to go, at most 1 long. Keys always give a full length; a stick
+    /// gives how far it's pushed, which the server scales acceleration by.
+    pub movement: Vec2,
+    /// Direction the right stick points, if it's pushed
+    pub aim: Option<Vec2>,
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl PlayerInput {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    chat_box::typing,
+    client::{ClientNetwork, Player, RemotePlayer},
+    controls::{Action, Controls, PlayerInput},
+    prediction::NetMotion,
+    protocol::{Ability, ClientMessage, DASH_SPEED},
+    screens::ClientState,
+    shooting::Stun,
+    spectator_camera::spectating,
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AbilityCooldowns>()
+        .add_systems(OnEnter(ClientState::Connecting), clear_cooldowns)
+        .add_systems(
+            OnEnter(ClientState::InGame),
+            spawn_cooldown_hud.run_if(not(spectating)),
+        )
+        .add_systems(
+            Update,
+            (
+                tick_cooldowns,
+                dash.run_if(not(typing)),
+                update_cooldown_hud,
+            )
+                .chain()
+                .run_if(in_state(ClientState::InGame)),
+        );
+}
+
+// === Abilities ===
+fn clear_cooldowns(mut cooldowns: ResMut<AbilityCooldowns>) {
+    cooldowns.0.clear();
+}
+
+fn tick_cooldowns(time: Res<Time>, mut cooldowns: ResMut<AbilityCooldowns>) {
+    for remaining in cooldowns.0.values_mut() {
+        *remaining = (*remaining - time.delta_secs()).max(0.0);
+    }
+}
+
+/// Dashes the way we're steering, or along our current heading if we aren't.
+/// The dash is predicted straight away; the server corrects the cooldown if it disagrees.
+fn dash(
+    input: Res<PlayerInput>,
+    stun: Res<Stun>,
+    mut cooldowns: ResMut<AbilityCooldowns>,
+    mut local_player: Query<&mut NetMotion, (With<Player>, Without<RemotePlayer>)>,
+    mut network: ClientNetwork,
+) {
+    let action = Action::Ability(Ability::Dash);
+    if !input.just_pressed(action) || !cooldowns.ready(Ability::Dash) || stun.active() {
+        return;
+    }
+    let Ok(mut motion) = local_player.single_mut() else {
+        return;
+    };
+
+    let mut direction = input.movement;
+    if direction == Vec2::ZERO {
+        direction = motion.velocity;
+    }
+
+    let direction = direction.normalize_or_zero();
+    if direction == Vec2::ZERO {
+        return;
+    }
+
+    motion.velocity += direction * DASH_SPEED;
+    cooldowns.set(Ability::Dash, Ability::Dash.cooldown_secs());
+    network.send(&ClientMessage::UseAbility {
+        ability: Ability::Dash,
+        direction,
+    });
+}
+
+// === Cooldown HUD ===
+fn spawn_cooldown_hud(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(ClientState::InGame),
+        CooldownText,
+        Text::default(),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            right: Val::Px(8.0),
+            ..default()
+        },
+    ));
+}
+
+fn update_cooldown_hud(
+    cooldowns: Res<AbilityCooldowns>,
+    controls: Res<Controls>,
+    mut text: Query<&mut Text, With<CooldownText>>,
+) {
+    let Ok(mut text) = text.single_mut() else {
+        return;
+    };
+
+    let remaining = cooldowns.0.get(&Ability::Dash).copied().unwrap_or(0.0);
+    text.0 = if remaining > 0.0 {
+        format!("Dash {remaining:.1}s")
+    } else {
+        let key = controls.describe(Action::Ability(Ability::Dash));
+        format!("Dash ready [{key}]")
+    };
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+struct CooldownText;
+
+/// Seconds until each of our abilities can be used again, as last predicted or reported
+#[derive(Resource, Default)]
+pub struct AbilityCooldowns(HashMap<Ability, f32>);
+
+impl AbilityCooldowns {
+    pub fn set(&mut self, ability: Ability, remaining_secs: f32) {
+        self.0.insert(ability, remaining_secs);
+    }
+
+    fn ready(&self, ability: Ability) -> bool {
+        self.0
+            .get(&ability)
+            .is_none_or(|remaining| *remaining <= 0.0)
+    }
+}
@@ -0,0 +1,434 @@
+use crate::{
+    BoxCollectable, PLAYER_LINEAR_DAMPING, ServerMessage, arg_value,
+    map::MapDefinition,
+    protocol::{COLLECT_RADIUS, DRONE_RADIUS, DroneData},
+    rounds::MatchState,
+    server::{CollectibleEntityMap, CollectibleId, GameRng, NetworkIds, ServerNetwork},
+};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_renet2::prelude::ServerEvent;
+use std::collections::VecDeque;
+
+/// Side of a navigation grid cell, in pixels
+const NAV_CELL_SIZE: f32 = 32.0;
+
+/// Extra room kept between a drone and walls when planning paths
+const NAV_MARGIN: f32 = 4.0;
+
+/// Seconds between drone position updates
+const DRONE_SYNC_SECS: f32 = 0.033;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(PostStartup, spawn_drones)
+        .add_systems(
+            Update,
+            (
+                rebuild_nav_grid.run_if(
+                    resource_changed::<MapDefinition>.and(not(resource_added::<MapDefinition>)),
+                ),
+                plan_drone_paths,
+                steer_drones,
+                drones_collect,
+                send_drones_on_connect,
+            )
+                .chain(),
+        )
+        .add_systems(
+            PostUpdate,
+            broadcast_drone_positions.in_set(PhysicsSet::Writeback),
+        );
+}
+
+// === Drones ===
+fn spawn_drones(
+    mut commands: Commands,
+    rules: Res<DroneRules>,
+    map: Res<MapDefinition>,
+    mut ids: ResMut<NetworkIds>,
+    mut rng: ResMut<GameRng>,
+) {
+    commands.insert_resource(NavGrid::new(&map, DRONE_RADIUS + NAV_MARGIN));
+
+    for _ in 0..rules.count {
+        let position = map.random_spawn_point(&mut **rng);
+        commands.spawn((
+            Drone {
+                id: ids.allocate(),
+                path: Vec::new(),
+                replan: Timer::from_seconds(rules.difficulty.replan_secs(), TimerMode::Repeating),
+            },
+            RigidBody::Dynamic,
+            Collider::ball(DRONE_RADIUS),
+            Velocity::zero(),
+            Damping {
+                linear_damping: PLAYER_LINEAR_DAMPING,
+                angular_damping: 2.0,
+            },
+            LockedAxes::ROTATION_LOCKED,
+            Transform::from_translation(position.extend(0.0)),
+            GlobalTransform::default(),
+        ));
+    }
+
+    if rules.count > 0 {
+        info!("Spawned {} {:?} drones", rules.count, rules.difficulty);
+    }
+}
+
+/// After an admin changes the map, plans paths around the new layout and moves drones out of
+/// any walls that now stand where they were
+fn rebuild_nav_grid(
+    mut commands: Commands,
+    map: Res<MapDefinition>,
+    mut rng: ResMut<GameRng>,
+    mut drones: Query<(&mut Drone, &mut Transform, &mut Velocity)>,
+) {
+    commands.insert_resource(NavGrid::new(&map, DRONE_RADIUS + NAV_MARGIN));
+
+    for (mut drone, mut transform, mut velocity) in drones.iter_mut() {
+        drone.path.clear();
+        transform.translation = map.random_spawn_point(&mut **rng).extend(0.0);
+        *velocity = Velocity::zero();
+    }
+}
+
+/// Every so often each drone picks the nearest box and plans a route to it around obstacles.
+/// Slower difficulties react less often.
+fn plan_drone_paths(
+    time: Res<Time>,
+    grid: Res<NavGrid>,
+    mut drones: Query<(&mut Drone, &Transform)>,
+    boxes: Query<&Transform, With<BoxCollectable>>,
+) {
+    for (mut drone, transform) in drones.iter_mut() {
+        let due = drone.replan.tick(time.delta()).just_finished();
+        if !due && !drone.path.is_empty() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let nearest = boxes
+            .iter()
+            .map(|target| target.translation.truncate())
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+
+        drone.path = nearest
+            .and_then(|target| grid.find_path(position, target))
+            .unwrap_or_default();
+    }
+}
+
+/// Pushes each drone towards its next waypoint, the same way player input pushes a player
+fn steer_drones(
+    time: Res<Time>,
+    rules: Res<DroneRules>,
+    mut drones: Query<(&mut Drone, &Transform, &mut Velocity)>,
+) {
+    let acceleration = rules.difficulty.max_speed() * PLAYER_LINEAR_DAMPING;
+
+    for (mut drone, transform, mut velocity) in drones.iter_mut() {
+        let position = transform.translation.truncate();
+        while drone
+            .path
+            .first()
+            .is_some_and(|waypoint| waypoint.distance(position) < NAV_CELL_SIZE / 2.0)
+        {
+            drone.path.remove(0);
+        }
+
+        if let Some(waypoint) = drone.path.first() {
+            let direction = (*waypoint - position).normalize_or_zero();
+            velocity.linvel += direction * acceleration * time.delta_secs();
+        }
+    }
+}
+
+/// Drones pick up any box they touch, taking it away from the players
+fn drones_collect(
+    mut commands: Commands,
+    match_state: Res<MatchState>,
+    mut collectible_entities: ResMut<CollectibleEntityMap>,
+    mut network: ServerNetwork,
+    drones: Query<(&Drone, &Transform)>,
+    boxes: Query<(Entity, &CollectibleId, &Transform), With<BoxCollectable>>,
+) {
+    if !match_state.allows_collecting() {
+        return;
+    }
+
+    for (entity, id, transform) in boxes.iter() {
+        let Some((drone, _)) = drones
+            .iter()
+            .find(|(_, drone)| drone.translation.distance(transform.translation) <= COLLECT_RADIUS)
+        else {
+            continue;
+        };
+
+        debug!("Drone {} took box {}", drone.id, id.0);
+        commands.entity(entity).despawn();
+        collectible_entities.0.remove(&id.0);
+        network.broadcast(&ServerMessage::DespawnCollectible { id: id.0 });
+    }
+}
+
+fn send_drones_on_connect(
+    mut events: EventReader<ServerEvent>,
+    mut network: ServerNetwork,
+    drones: Query<(&Drone, &Transform, &Velocity)>,
+) {
+    for event in events.read() {
+        if let ServerEvent::ClientConnected { client_id } = event {
+            network.send(*client_id, &ServerMessage::SpawnDrones(drone_data(&drones)));
+        }
+    }
+}
+
+fn broadcast_drone_positions(
+    time: Res<Time>,
+    mut since_last: Local<f32>,
+    mut network: ServerNetwork,
+    drones: Query<(&Drone, &Transform, &Velocity)>,
+) {
+    *since_last += time.delta_secs();
+    if *since_last < DRONE_SYNC_SECS || drones.is_empty() {
+        return;
+    }
+    *since_last = 0.0;
+
+    network.broadcast(&ServerMessage::DronePositions(drone_data(&drones)));
+}
+
+fn drone_data(drones: &Query<(&Drone, &Transform, &Velocity)>) -> Vec<DroneData> {
+    drones
+        .iter()
+        .map(|(drone, transform, velocity)| DroneData {
+            id: drone.id,
+            position: transform.translation,
+            velocity: velocity.linvel,
+        })
+        .collect()
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+pub struct Drone {
+    id: u64,
+    /// Waypoints still to visit, nearest first
+    path: Vec<Vec2>,
+    replan: Timer,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Top speed, for comparison a player's is 200
+    fn max_speed(self) -> f32 {
+        match self {
+            Difficulty::Easy => 110.0,
+            Difficulty::Normal => 160.0,
+            Difficulty::Hard => 210.0,
+        }
+    }
+
+    /// Seconds between choosing a new target
+    fn replan_secs(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1.5,
+            Difficulty::Normal => 0.75,
+            Difficulty::Hard => 0.3,
+        }
+    }
+}
+
+/// How many drones to run and how good they are, set from the command line
+#[derive(Resource)]
+pub struct DroneRules {
+    pub count: usize,
+    pub difficulty: Difficulty,
+}
+
+impl DroneRules {
+    pub fn from_args(args: &[String]) -> Self {
+        let difficulty = match arg_value(args, "--drone-difficulty") {
+            Some("easy") => Difficulty::Easy,
+            Some("hard") => Difficulty::Hard,
+            _ => Difficulty::Normal,
+        };
+
+        Self {
+            count: arg_value(args, "--drones")
+                .and_then(|count| count.parse().ok())
+                .unwrap_or(0),
+            difficulty,
+        }
+    }
+}
+
+/// The map divided into cells, each either open or blocked by a solid
+#[derive(Resource)]
+pub struct NavGrid {
+    /// World position of the corner of cell (0, 0)
+    origin: Vec2,
+    columns: i32,
+    rows: i32,
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    /// Cells whose center is within `clearance` of a solid are blocked
+    fn new(map: &MapDefinition, clearance: f32) -> Self {
+        let origin = -map.half_extents;
+        let columns = (map.half_extents.x * 2.0 / NAV_CELL_SIZE).ceil() as i32;
+        let rows = (map.half_extents.y * 2.0 / NAV_CELL_SIZE).ceil() as i32;
+
+        let mut grid = Self {
+            origin,
+            columns,
+            rows,
+            blocked: Vec::new(),
+        };
+        grid.blocked = (0..rows)
+            .flat_map(|y| (0..columns).map(move |x| IVec2::new(x, y)))
+            .map(|cell| {
+                let center = grid.center(cell);
+                map.solids().any(|solid| solid.contains(center, clearance))
+            })
+            .collect();
+        grid
+    }
+
+    fn cell(&self, point: Vec2) -> IVec2 {
+        ((point - self.origin) / NAV_CELL_SIZE)
+            .floor()
+            .as_ivec2()
+            .clamp(IVec2::ZERO, IVec2::new(self.columns - 1, self.rows - 1))
+    }
+
+    fn center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * NAV_CELL_SIZE
+    }
+
+    fn index(&self, cell: IVec2) -> Option<usize> {
+        let inside = cell.x >= 0 && cell.y >= 0 && cell.x < self.columns && cell.y < self.rows;
+        inside.then(|| (cell.y * self.columns + cell.x) as usize)
+    }
+
+    fn is_open(&self, cell: IVec2) -> bool {
+        self.index(cell).is_some_and(|index| !self.blocked[index])
+    }
+
+    /// Breadth-first search over open cells, moving diagonally only where both sides are
+    /// open so drones don't clip corners. The last waypoint is `to` itself.
+    fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        let start = self.cell(from);
+        let goal = self.cell(to);
+        if !self.is_open(goal) {
+            return None;
+        }
+
+        let mut came_from = vec![None; self.blocked.len()];
+        let mut queue = VecDeque::from([start]);
+        came_from[self.index(start)?] = Some(start);
+
+        while let Some(cell) = queue.pop_front() {
+            if cell == goal {
+                let mut path = vec![to];
+                let mut step = cell;
+                while step != start {
+                    step = came_from[self.index(step)?]?;
+                    if step != start {
+                        path.push(self.center(step));
+                    }
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let next = cell + IVec2::new(dx, dy);
+                    let diagonal_clear = (dx == 0 || dy == 0)
+                        || (self.is_open(cell + IVec2::new(dx, 0))
+                            && self.is_open(cell + IVec2::new(0, dy)));
+                    if next == cell || !self.is_open(next) || !diagonal_clear {
+                        continue;
+                    }
+
+                    let index = self.index(next)?;
+                    if came_from[index].is_none() {
+                        came_from[index] = Some(cell);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 320 by 320 arena with a wall down the middle that leaves a gap at the top
+    fn split_arena(wall_height: f32) -> NavGrid {
+        let map: MapDefinition = ron::from_str(&format!(
+            "(
+                name: \"Test\",
+                half_extents: (160.0, 160.0),
+                walls: [(center: (0.0, {}), size: (16.0, {wall_height}))],
+                spawn_zones: [],
+            )",
+            wall_height / 2.0 - 160.0
+        ))
+        .unwrap();
+        NavGrid::new(&map, DRONE_RADIUS + NAV_MARGIN)
+    }
+
+    #[test]
+    fn path_goes_around_walls() {
+        let grid = split_arena(192.0);
+        let from = Vec2::new(-100.0, -100.0);
+        let to = Vec2::new(100.0, -100.0);
+        let path = grid.find_path(from, to).unwrap();
+
+        assert_eq!(path.last(), Some(&to));
+        assert!(
+            path.iter().any(|point| point.y > 48.0),
+            "goes through the gap"
+        );
+        let mut previous = from;
+        for point in &path[..path.len() - 1] {
+            assert!(grid.is_open(grid.cell(*point)));
+            assert!(previous.distance(*point) <= NAV_CELL_SIZE * 2.0);
+            previous = *point;
+        }
+    }
+
+    #[test]
+    fn path_to_the_same_cell_is_just_the_goal() {
+        let grid = split_arena(192.0);
+        let to = Vec2::new(-90.0, -90.0);
+        assert_eq!(
+            grid.find_path(Vec2::new(-100.0, -100.0), to),
+            Some(vec![to])
+        );
+    }
+
+    #[test]
+    fn no_path_into_walls_or_closed_off_areas() {
+        let grid = split_arena(192.0);
+        assert_eq!(grid.find_path(Vec2::new(-100.0, -100.0), Vec2::ZERO), None);
+
+        let closed = split_arena(320.0);
+        let from = Vec2::new(-100.0, -100.0);
+        assert_eq!(closed.find_path(from, Vec2::new(100.0, -100.0)), None);
+    }
+}
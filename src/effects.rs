@@ -0,0 +1,158 @@
+use crate::{
+    client::{ClientInfo, Player, RemotePlayer},
+    protocol::PowerUp,
+    screens::ClientState,
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PlayerEffects>()
+        .add_systems(OnEnter(ClientState::Connecting), clear_effects)
+        .add_systems(OnEnter(ClientState::InGame), spawn_effects_hud)
+        .add_systems(
+            Update,
+            (tick_effects, sync_auras, update_effects_hud)
+                .chain()
+                .run_if(in_state(ClientState::InGame)),
+        );
+}
+
+// === Effects ===
+fn clear_effects(mut effects: ResMut<PlayerEffects>) {
+    effects.0.clear();
+}
+
+/// Counts down locally; the server says when an effect really ends
+fn tick_effects(time: Res<Time>, mut effects: ResMut<PlayerEffects>) {
+    for active in effects.0.values_mut() {
+        for (_, remaining) in active.iter_mut() {
+            *remaining = (*remaining - time.delta_secs()).max(0.0);
+        }
+    }
+}
+
+/// Gives each player a glow per active power-up
+fn sync_auras(
+    mut commands: Commands,
+    effects: Res<PlayerEffects>,
+    client_info: Res<ClientInfo>,
+    players: Query<(Entity, Option<&RemotePlayer>), With<Player>>,
+    auras: Query<(Entity, &EffectAura, &ChildOf)>,
+) {
+    let client_id = |entity: Entity| {
+        players
+            .get(entity)
+            .ok()
+            .and_then(|(_, remote)| remote.map(|r| r.client_id).or(client_info.id))
+    };
+
+    for (entity, aura, parent) in auras.iter() {
+        let active = client_id(parent.parent()).is_some_and(|id| effects.has(id, aura.0));
+        if !active {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for (entity, _) in players.iter() {
+        let Some(id) = client_id(entity) else {
+            continue;
+        };
+
+        for (power_up, _) in effects.0.get(&id).into_iter().flatten() {
+            let has_aura = auras
+                .iter()
+                .any(|(_, aura, parent)| parent.parent() == entity && aura.0 == *power_up);
+            if !has_aura {
+                commands.entity(entity).with_child(aura(*power_up));
+            }
+        }
+    }
+}
+
+fn aura(power_up: PowerUp) -> impl Bundle {
+    let size = match power_up {
+        PowerUp::SpeedBoost => 38.0,
+        PowerUp::Shield => 46.0,
+        PowerUp::Magnet => 54.0,
+    };
+
+    (
+        EffectAura(power_up),
+        Sprite {
+            color: power_up.color().with_alpha(0.35),
+            custom_size: Some(Vec2::splat(size)),
+            ..default()
+        },
+        // Behind the player's own square
+        Transform::from_xyz(0.0, 0.0, -0.5),
+    )
+}
+
+// === Effects HUD ===
+fn spawn_effects_hud(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(ClientState::InGame),
+        EffectsText,
+        Text::default(),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+    ));
+}
+
+/// Lists our own active power-ups with the time they have left
+fn update_effects_hud(
+    effects: Res<PlayerEffects>,
+    client_info: Res<ClientInfo>,
+    mut text: Query<&mut Text, With<EffectsText>>,
+) {
+    let Ok(mut text) = text.single_mut() else {
+        return;
+    };
+
+    let active = client_info.id.and_then(|id| effects.0.get(&id));
+    text.0 = active
+        .into_iter()
+        .flatten()
+        .map(|(power_up, remaining)| format!("{power_up:?} {:.0}s", remaining.ceil()))
+        .collect::<Vec<_>>()
+        .join("\n");
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+struct EffectAura(PowerUp);
+
+#[derive(Component)]
+struct EffectsText;
+
+/// Power-ups each player has active, with roughly how long they have left
+#[derive(Resource, Default)]
+pub struct PlayerEffects(pub HashMap<u64, Vec<(PowerUp, f32)>>);
+
+impl PlayerEffects {
+    pub fn start(&mut self, client_id: u64, power_up: PowerUp, remaining_secs: f32) {
+        let active = self.0.entry(client_id).or_default();
+        active.retain(|(active, _)| *active != power_up);
+        active.push((power_up, remaining_secs));
+    }
+
+    pub fn end(&mut self, client_id: u64, power_up: PowerUp) {
+        if let Some(active) = self.0.get_mut(&client_id) {
+            active.retain(|(active, _)| *active != power_up);
+        }
+    }
+
+    pub fn has(&self, client_id: u64, power_up: PowerUp) -> bool {
+        self.0
+            .get(&client_id)
+            .is_some_and(|active| active.iter().any(|(active, _)| *active == power_up))
+    }
+}
@@ -0,0 +1,347 @@
+use crate::{
+    ServerMessage,
+    admin::Kicks,
+    protocol::{ChatScope, ClientMessage, INPUT_INTERVAL_SECS},
+    server::ServerNetwork,
+};
+use bevy::{ecs::system::SystemParam, platform::collections::HashMap, prelude::*};
+use bevy_renet2::prelude::ServerEvent;
+use std::net::IpAddr;
+
+/// Clients send movement at most 60 times a second whatever their frame rate. The burst
+/// leaves room for input held up by the network arriving in a bunch.
+const INPUT_LIMIT: Limit = Limit {
+    burst: 60.0,
+    per_sec: 1.0 / INPUT_INTERVAL_SECS,
+};
+
+/// Seconds of movement a client's inputs can claim, refilled only as real time passes, so
+/// however the inputs are sliced a player can't be pushed for longer than has gone by
+const MOVEMENT_TIME_LIMIT: Limit = Limit {
+    burst: 0.5,
+    per_sec: 1.0,
+};
+
+/// Clients ask once a second for each box they overlap, until it despawns
+const COLLECT_LIMIT: Limit = Limit {
+    burst: 30.0,
+    per_sec: 120.0,
+};
+
+/// Dashes and shots, which the server cools down anyway
+const ACTION_LIMIT: Limit = Limit {
+    burst: 10.0,
+    per_sec: 10.0,
+};
+
+/// A coarse cap only, chat has its own slower allowance on top
+const CHAT_LIMIT: Limit = Limit {
+    burst: 10.0,
+    per_sec: 2.0,
+};
+
+/// Connection attempts from one address before it has to wait
+const CONNECT_LIMIT: Limit = Limit {
+    burst: 5.0,
+    per_sec: 1.0 / 12.0,
+};
+
+/// Dropped messages that earn a warning
+const DROPS_PER_WARNING: u32 = 60;
+
+/// Warnings a client gets before being kicked
+const MAX_WARNINGS: u32 = 2;
+
+/// Seconds without a dropped message after which a client's record is wiped clean
+const FORGIVE_SECS: f32 = 30.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<MessageLimits>()
+        .init_resource::<ConnectThrottle>()
+        .add_systems(Update, forget_disconnected);
+}
+
+fn forget_disconnected(mut events: EventReader<ServerEvent>, mut limits: ResMut<MessageLimits>) {
+    for event in events.read() {
+        if let ServerEvent::ClientDisconnected { client_id, .. } = event {
+            limits.0.remove(client_id);
+        }
+    }
+}
+
+// === Components and Resources ===
+/// How many of something are allowed at once, and how fast that comes back
+pub struct Limit {
+    pub burst: f32,
+    pub per_sec: f32,
+}
+
+/// What's left of a [`Limit`], refilling over time
+pub struct Allowance {
+    left: f32,
+    /// Elapsed time the allowance was last brought up to date
+    updated: f32,
+}
+
+impl Allowance {
+    pub fn full(limit: &Limit, now: f32) -> Self {
+        Self {
+            left: limit.burst,
+            updated: now,
+        }
+    }
+
+    /// Takes as much of `amount` as is left, returning how much that was
+    pub fn take(&mut self, limit: &Limit, now: f32, amount: f32) -> f32 {
+        self.refill(limit, now);
+        let taken = amount.clamp(0.0, self.left.max(0.0));
+        self.left -= taken;
+        taken
+    }
+
+    pub fn spend(&mut self, limit: &Limit, now: f32) -> bool {
+        self.refill(limit, now);
+        if self.left < 1.0 {
+            return false;
+        }
+        self.left -= 1.0;
+        true
+    }
+
+    fn refill(&mut self, limit: &Limit, now: f32) {
+        let refilled = (now - self.updated) * limit.per_sec;
+        self.left = (self.left + refilled).min(limit.burst);
+        self.updated = now;
+    }
+}
+
+/// Which allowance a message is spent from
+#[derive(Clone, Copy)]
+enum MessageClass {
+    Input,
+    Collect,
+    Action,
+    Chat,
+}
+
+impl MessageClass {
+    const ALL: [MessageClass; 4] = [
+        MessageClass::Input,
+        MessageClass::Collect,
+        MessageClass::Action,
+        MessageClass::Chat,
+    ];
+
+    fn of(message: &ClientMessage) -> Self {
+        match message {
+            ClientMessage::MoveInput { .. } => MessageClass::Input,
+            ClientMessage::AttemptCollect { .. } => MessageClass::Collect,
+            ClientMessage::UseAbility { .. } | ClientMessage::Fire { .. } => MessageClass::Action,
+            ClientMessage::Chat { .. } => MessageClass::Chat,
+        }
+    }
+
+    fn limit(self) -> &'static Limit {
+        match self {
+            MessageClass::Input => &INPUT_LIMIT,
+            MessageClass::Collect => &COLLECT_LIMIT,
+            MessageClass::Action => &ACTION_LIMIT,
+            MessageClass::Chat => &CHAT_LIMIT,
+        }
+    }
+}
+
+/// A client's allowances and how far it has pushed past them
+struct ClientLimits {
+    allowances: [Allowance; 4],
+    movement_time: Allowance,
+    dropped: u32,
+    warnings: u32,
+    last_dropped: f32,
+}
+
+impl ClientLimits {
+    fn new(now: f32) -> Self {
+        Self {
+            allowances: MessageClass::ALL.map(|class| Allowance::full(class.limit(), now)),
+            movement_time: Allowance::full(&MOVEMENT_TIME_LIMIT, now),
+            dropped: 0,
+            warnings: 0,
+            last_dropped: now,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct MessageLimits(HashMap<u64, ClientLimits>);
+
+/// Whether a client's message should be handled. Over its limit the message is dropped, a
+/// client that keeps at it is warned, and one that ignores its warnings is kicked.
+#[derive(SystemParam)]
+pub struct FloodGuard<'w> {
+    time: Res<'w, Time>,
+    limits: ResMut<'w, MessageLimits>,
+    kicks: ResMut<'w, Kicks>,
+}
+
+impl FloodGuard<'_> {
+    /// How much of the movement time an input claims the client still has to spend
+    pub fn movement_time(&mut self, client_id: u64, claimed: f32) -> f32 {
+        let now = self.time.elapsed_secs();
+        self.limits
+            .0
+            .entry(client_id)
+            .or_insert_with(|| ClientLimits::new(now))
+            .movement_time
+            .take(&MOVEMENT_TIME_LIMIT, now, claimed)
+    }
+
+    pub fn allow(
+        &mut self,
+        network: &mut ServerNetwork,
+        client_id: u64,
+        message: &ClientMessage,
+    ) -> bool {
+        // Anything from a client on its way out is ignored
+        if self.kicks.contains(client_id) {
+            return false;
+        }
+
+        let now = self.time.elapsed_secs();
+        let client = self
+            .limits
+            .0
+            .entry(client_id)
+            .or_insert_with(|| ClientLimits::new(now));
+        let class = MessageClass::of(message);
+        if client.allowances[class as usize].spend(class.limit(), now) {
+            return true;
+        }
+
+        if now - client.last_dropped > FORGIVE_SECS {
+            client.dropped = 0;
+            client.warnings = 0;
+        }
+        client.dropped += 1;
+        client.last_dropped = now;
+        if client.dropped < DROPS_PER_WARNING {
+            return false;
+        }
+
+        client.dropped = 0;
+        client.warnings += 1;
+        if client.warnings > MAX_WARNINGS {
+            warn!("Kicking {client_id} for flooding after {MAX_WARNINGS} warnings");
+            self.kicks
+                .kick(network, client_id, "Sending too many messages");
+            return false;
+        }
+
+        warn!(
+            "Client {client_id} is flooding {} messages, warning {}",
+            message.kind(),
+            client.warnings
+        );
+        network.send(
+            client_id,
+            &ServerMessage::Chat {
+                scope: ChatScope::Server,
+                from: String::new(),
+                text: format!(
+                    "You're sending too many messages and will be kicked if it continues ({}/{MAX_WARNINGS})",
+                    client.warnings
+                ),
+            },
+        );
+        false
+    }
+}
+
+/// Connection attempts per address, so one machine can't churn through the server's slots
+#[derive(Resource, Default)]
+pub struct ConnectThrottle(HashMap<IpAddr, Allowance>);
+
+impl ConnectThrottle {
+    pub fn allow(&mut self, ip: IpAddr, now: f32) -> bool {
+        // Addresses that have waited long enough to be back to a full allowance are forgotten
+        let full_after = CONNECT_LIMIT.burst / CONNECT_LIMIT.per_sec;
+        self.0
+            .retain(|_, allowance| now - allowance.updated < full_after);
+
+        self.0
+            .entry(ip)
+            .or_insert_with(|| Allowance::full(&CONNECT_LIMIT, now))
+            .spend(&CONNECT_LIMIT, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::INPUT_INTERVAL_SECS;
+
+    const LIMIT: Limit = Limit {
+        burst: 3.0,
+        per_sec: 2.0,
+    };
+
+    #[test]
+    fn allowance_spends_a_burst_then_refills() {
+        let mut allowance = Allowance::full(&LIMIT, 0.0);
+        assert!((0..3).all(|_| allowance.spend(&LIMIT, 0.0)));
+        assert!(!allowance.spend(&LIMIT, 0.0));
+        assert!(!allowance.spend(&LIMIT, 0.25));
+        assert!(allowance.spend(&LIMIT, 0.5));
+
+        // A long wait only refills up to the burst
+        assert!((0..3).all(|_| allowance.spend(&LIMIT, 100.0)));
+        assert!(!allowance.spend(&LIMIT, 100.0));
+    }
+
+    #[test]
+    fn steady_client_input_stays_within_its_limit() {
+        let mut allowance = Allowance::full(&INPUT_LIMIT, 0.0);
+        for tick in 0..600 {
+            let now = tick as f32 * INPUT_INTERVAL_SECS;
+            assert!(allowance.spend(&INPUT_LIMIT, now));
+        }
+
+        // A second's worth held up by the network and then arriving at once still gets through
+        let now = 11.0;
+        assert!((0..60).all(|_| allowance.spend(&INPUT_LIMIT, now)));
+    }
+
+    #[test]
+    fn movement_time_cannot_outrun_the_clock() {
+        let mut allowance = Allowance::full(&MOVEMENT_TIME_LIMIT, 0.0);
+        let mut granted = 0.0;
+        // Four inputs' worth of movement every tick, as a speed hack would send
+        for tick in 1..=600 {
+            let now = tick as f32 * INPUT_INTERVAL_SECS;
+            for _ in 0..4 {
+                granted += allowance.take(&MOVEMENT_TIME_LIMIT, now, INPUT_INTERVAL_SECS);
+            }
+        }
+        assert!(granted <= 10.0 + MOVEMENT_TIME_LIMIT.burst + 1e-3);
+
+        // An honest client never runs short
+        let mut allowance = Allowance::full(&MOVEMENT_TIME_LIMIT, 0.0);
+        for tick in 1..=600 {
+            let now = tick as f32 * INPUT_INTERVAL_SECS;
+            let taken = allowance.take(&MOVEMENT_TIME_LIMIT, now, INPUT_INTERVAL_SECS);
+            assert_eq!(taken, INPUT_INTERVAL_SECS);
+        }
+    }
+
+    #[test]
+    fn connect_throttle_is_per_address() {
+        let mut throttle = ConnectThrottle::default();
+        let first: IpAddr = [10, 0, 0, 1].into();
+        let second: IpAddr = [10, 0, 0, 2].into();
+
+        assert!((0..5).all(|_| throttle.allow(first, 0.0)));
+        assert!(!throttle.allow(first, 0.0));
+        assert!(throttle.allow(second, 0.0));
+        assert!(throttle.allow(first, 12.0));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    protocol::{CollectibleKind, MAX_CARRY_WEIGHT, MatchPhase, RoundStanding, Team},
+    screens::ClientState,
+};
+use bevy::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<MatchHud>()
+        .add_systems(OnEnter(ClientState::Connecting), reset_match_hud)
+        .add_systems(OnEnter(ClientState::InGame), spawn_match_hud)
+        .add_systems(
+            Update,
+            (tick_match_timer, update_match_timer, update_round_results)
+                .chain()
+                .run_if(in_state(ClientState::InGame)),
+        );
+}
+
+// === Match HUD ===
+fn reset_match_hud(mut hud: ResMut<MatchHud>) {
+    *hud = MatchHud::default();
+}
+
+fn spawn_match_hud(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(ClientState::InGame),
+        MatchTimerText,
+        Text::default(),
+        TextFont {
+            font_size: 22.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+    ));
+
+    commands.spawn((
+        StateScoped(ClientState::InGame),
+        RoundResultsPanel,
+        Text::default(),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(25.0),
+            left: Val::Percent(30.0),
+            width: Val::Percent(40.0),
+            padding: UiRect::all(Val::Px(16.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+        Visibility::Hidden,
+    ));
+}
+
+/// Counts down locally between the server's phase updates
+fn tick_match_timer(time: Res<Time>, mut hud: ResMut<MatchHud>) {
+    if let Some(remaining) = hud.remaining_secs.as_mut() {
+        *remaining = (*remaining - time.delta_secs()).max(0.0);
+    }
+}
+
+fn update_match_timer(hud: Res<MatchHud>, mut text: Query<&mut Text, With<MatchTimerText>>) {
+    let Ok(mut text) = text.single_mut() else {
+        return;
+    };
+
+    let Some(phase) = hud.phase else {
+        text.0.clear();
+        return;
+    };
+
+    let remaining = hud.remaining_secs.map(|secs| secs.ceil() as u32);
+    let timer = match (phase, remaining) {
+        (MatchPhase::Warmup, Some(secs)) => format!("Warmup - starting in {secs}"),
+        (MatchPhase::Warmup, None) => "Warmup - waiting for players".to_string(),
+        (MatchPhase::Countdown, secs) => {
+            format!("Round {} starts in {}", hud.round, secs.unwrap_or(0))
+        }
+        (MatchPhase::Playing, Some(secs)) => {
+            format!("Round {}  {}:{:02}", hud.round, secs / 60, secs % 60)
+        }
+        (MatchPhase::Playing, None) => format!("Round {}", hud.round),
+        (MatchPhase::RoundOver, _) => format!("Round {} over", hud.round),
+        (MatchPhase::Intermission, secs) => {
+            format!("Next round in {}", secs.unwrap_or(0))
+        }
+    };
+
+    let mut lines = vec![timer];
+    if !hud.team_scores.is_empty() {
+        lines.push(
+            hud.team_scores
+                .iter()
+                .map(|(team, score)| format!("{team:?} {score}"))
+                .collect::<Vec<_>>()
+                .join("  -  "),
+        );
+    }
+    if !hud.carrying.is_empty() {
+        let points: u32 = hud.carrying.iter().map(|kind| kind.points()).sum();
+        lines.push(format!(
+            "Carrying {points} (weight {}/{MAX_CARRY_WEIGHT}) - deliver it to score",
+            hud.carried_weight()
+        ));
+    }
+    text.0 = lines.join("\n");
+}
+
+/// Shows the last round's results until the next round starts
+fn update_round_results(
+    hud: Res<MatchHud>,
+    mut panel: Query<(&mut Text, &mut Visibility), With<RoundResultsPanel>>,
+) {
+    let Ok((mut text, mut visibility)) = panel.single_mut() else {
+        return;
+    };
+
+    let showing = matches!(
+        hud.phase,
+        Some(MatchPhase::RoundOver | MatchPhase::Intermission)
+    );
+    let Some(results) = hud.results.as_ref().filter(|_| showing) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    *visibility = Visibility::Inherited;
+    text.0 = results.summary();
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+struct MatchTimerText;
+
+#[derive(Component)]
+struct RoundResultsPanel;
+
+pub struct RoundResults {
+    pub round: u32,
+    pub winner: Option<u64>,
+    pub winning_team: Option<Team>,
+    pub standings: Vec<RoundStanding>,
+}
+
+impl RoundResults {
+    fn summary(&self) -> String {
+        let winner = self
+            .winner
+            .and_then(|id| self.standings.iter().find(|s| s.client_id == id))
+            .map(|s| format!("{} wins!", s.name))
+            .or_else(|| self.winning_team.map(|team| format!("{team:?} team wins!")))
+            .unwrap_or_else(|| "Draw".to_string());
+
+        let table = self
+            .standings
+            .iter()
+            .enumerate()
+            .map(|(i, s)| match s.team {
+                Some(team) => format!("{}. [{team:?}] {}  {}", i + 1, s.name, s.score),
+                None => format!("{}. {}  {}", i + 1, s.name, s.score),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        format!("Round {} results\n{winner}\n\n{table}", self.round)
+    }
+}
+
+/// Match progress as last reported by the server
+#[derive(Resource, Default)]
+pub struct MatchHud {
+    pub phase: Option<MatchPhase>,
+    pub round: u32,
+    pub remaining_secs: Option<f32>,
+    pub results: Option<RoundResults>,
+    /// Only filled in team mode
+    pub team_scores: Vec<(Team, u32)>,
+    /// Boxes we're carrying to a drop-off in team or delivery mode
+    pub carrying: Vec<CollectibleKind>,
+}
+
+impl MatchHud {
+    pub fn carried_weight(&self) -> u32 {
+        self.carrying.iter().map(|kind| kind.weight()).sum()
+    }
+
+    pub fn set_team_score(&mut self, team: Team, score: u32) {
+        match self.team_scores.iter_mut().find(|(t, _)| *t == team) {
+            Some((_, current)) => *current = score,
+            None => {
+                self.team_scores.push((team, score));
+                self.team_scores.sort_by_key(|(team, _)| *team as u8);
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{
+    ClientMessage, ServerMessage, arg_value,
+    recording::{Recording, Route},
+};
+use bevy::prelude::*;
+use std::{fmt::Write, time::Instant};
+
+const HEX_ROW_BYTES: usize = 16;
+
+/// Pretty-prints every packet in a recording
+pub fn run(args: &[String]) {
+    let Some(path) = args.first() else {
+        eprintln!(
+            "Usage: cargo run --bin main -- inspect <file> [--type <Kind,...>] [--client <id>]"
+        );
+        return;
+    };
+
+    let recording = match Recording::load(path) {
+        Ok(recording) => recording,
+        Err(e) => {
+            eprintln!("Failed to load recording {path}: {e}");
+            return;
+        }
+    };
+
+    let filter = InspectFilter::from_args(args);
+    for packet in &recording.packets {
+        if let Some(text) = filter.describe(
+            packet.time,
+            Some(packet.tick),
+            packet.route,
+            packet.channel,
+            &packet.payload,
+        ) {
+            println!("{text}");
+        }
+    }
+}
+
+/// Selects which packets get printed
+#[derive(Default, Clone)]
+pub struct InspectFilter {
+    /// Message kinds to show, e.g. `MoveInput`; empty shows everything
+    pub kinds: Vec<String>,
+    /// Only show traffic to or from this client. Broadcasts are always shown since every
+    /// client receives them.
+    pub client_id: Option<u64>,
+}
+
+impl InspectFilter {
+    pub fn from_args(args: &[String]) -> Self {
+        Self {
+            kinds: arg_value(args, "--type")
+                .map(|kinds| kinds.split(',').map(String::from).collect())
+                .unwrap_or_default(),
+            client_id: arg_value(args, "--client").and_then(|id| id.parse().ok()),
+        }
+    }
+
+    fn matches_peer(&self, route: Route) -> bool {
+        match (self.client_id, route) {
+            (None, _) | (_, Route::Broadcast) => true,
+            (Some(id), Route::FromClient { client_id } | Route::ToClient { client_id }) => {
+                id == client_id
+            }
+        }
+    }
+
+    fn matches_kind(&self, kind: &str) -> bool {
+        self.kinds.is_empty() || self.kinds.iter().any(|k| k == kind)
+    }
+
+    /// Formats a packet, or returns `None` if the filter excludes it. Payloads that fail to
+    /// decode are always shown as a hex dump, whatever the kind filter says.
+    pub fn describe(
+        &self,
+        time: f64,
+        tick: Option<u64>,
+        route: Route,
+        channel: u8,
+        payload: &[u8],
+    ) -> Option<String> {
+        if !self.matches_peer(route) {
+            return None;
+        }
+
+        let decoded = match route {
+            Route::FromClient { .. } => {
+                ClientMessage::decode(payload).map(|msg| (msg.kind(), format!("{msg:#?}")))
+            }
+            Route::ToClient { .. } | Route::Broadcast => {
+                bincode::serde::decode_from_slice::<ServerMessage, _>(
+                    payload,
+                    bincode::config::standard(),
+                )
+                .map(|(msg, _)| (msg.kind(), format!("{msg:#?}")))
+            }
+        };
+
+        let (kind, body) = match decoded {
+            Ok((kind, body)) => {
+                if !self.matches_kind(kind) {
+                    return None;
+                }
+                (kind.to_string(), body)
+            }
+            Err(e) => (format!("<undecodable: {e}>"), hex_dump(payload)),
+        };
+
+        let tick = tick.map(|t| format!("tick {t} ")).unwrap_or_default();
+        let peer = match route {
+            Route::FromClient { client_id } => format!("client {client_id} -> server"),
+            Route::ToClient { client_id } => format!("server -> client {client_id}"),
+            Route::Broadcast => "server -> all".to_string(),
+        };
+
+        Some(format!(
+            "[{time:>10.3}s] {tick}ch{channel} {peer} {} B {kind}\n{}",
+            payload.len(),
+            indent(&body),
+        ))
+    }
+}
+
+/// Prints live traffic when the server or client is started with `--inspect`
+#[derive(Resource)]
+pub struct Inspector {
+    enabled: bool,
+    filter: InspectFilter,
+    started: Instant,
+}
+
+impl Inspector {
+    pub fn from_args(args: &[String]) -> Self {
+        Self {
+            enabled: args.iter().any(|arg| arg == "--inspect"),
+            filter: InspectFilter::from_args(args),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn inspect(&self, tick: Option<u64>, route: Route, channel: u8, payload: &[u8]) {
+        if !self.enabled {
+            return;
+        }
+
+        let time = self.started.elapsed().as_secs_f64();
+        if let Some(text) = self.filter.describe(time, tick, route, channel, payload) {
+            println!("{text}");
+        }
+    }
+}
+
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (row, chunk) in bytes.chunks(HEX_ROW_BYTES).enumerate() {
+        let _ = write!(out, "{:08x}  ", row * HEX_ROW_BYTES);
+        for i in 0..HEX_ROW_BYTES {
+            match chunk.get(i) {
+                Some(byte) => {
+                    let _ = write!(out, "{byte:02x} ");
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push(' ');
+        out.extend(chunk.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        out.push('\n');
+    }
+    out
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,211 @@
+use crate::{
+    CollectibleInfo, ServerMessage,
+    map::{MapDefinition, MapRect},
+    protocol::{CollectibleKind, MAX_CARRY_WEIGHT, PlayerProfile, carry_multiplier},
+    rounds::{GameMode, MatchRules, MatchState},
+    server::{
+        CollectibleEntityMap, DisconnectedPlayer, NetworkIds, Player, Score, ServerNetwork,
+        spawn_collectible,
+    },
+    teams::TeamScores,
+};
+use bevy::prelude::*;
+use bevy_renet2::prelude::ServerEvent;
+use std::f32::consts::TAU;
+
+/// How far from a player the boxes they drop land
+const DROP_SCATTER_RADIUS: f32 = 50.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            deliver_items,
+            drop_on_disconnect,
+            send_inventories_on_connect,
+        )
+            .run_if(carry_mode),
+    );
+}
+
+fn carry_mode(rules: Res<MatchRules>) -> bool {
+    rules.carries()
+}
+
+/// Spawns boxes in a ring around `center`, one per item, and returns them for broadcasting
+pub fn scatter_items(
+    commands: &mut Commands,
+    collectible_entities: &mut CollectibleEntityMap,
+    ids: &mut NetworkIds,
+    map: &MapDefinition,
+    center: Vec2,
+    items: &[CollectibleKind],
+) -> Vec<CollectibleInfo> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, kind)| {
+            let angle = TAU * i as f32 / items.len() as f32;
+            let spot = center + Vec2::from_angle(angle) * DROP_SCATTER_RADIUS;
+            // Boxes that would land in a wall drop where the player stands instead
+            let spot = if map.solids().any(|solid| solid.contains(spot, 0.0)) {
+                center
+            } else {
+                spot
+            };
+            spawn_collectible(commands, collectible_entities, ids, spot.extend(0.0), *kind)
+        })
+        .collect()
+}
+
+// === Inventory ===
+/// Banks what a player is carrying once they step into a drop-off: their own base in team
+/// mode, or any of the map's drop-offs in delivery mode
+fn deliver_items(
+    map: Res<MapDefinition>,
+    rules: Res<MatchRules>,
+    match_state: Res<MatchState>,
+    mut team_scores: ResMut<TeamScores>,
+    mut network: ServerNetwork,
+    mut players: Query<
+        (
+            &Player,
+            &PlayerProfile,
+            &Transform,
+            &mut Inventory,
+            &mut Score,
+        ),
+        Without<DisconnectedPlayer>,
+    >,
+) {
+    if !match_state.allows_collecting() {
+        return;
+    }
+
+    for (player, profile, transform, mut inventory, mut score) in players.iter_mut() {
+        if inventory.0.is_empty() {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        let at_drop_off = |zone: &MapRect| zone.contains(position, 0.0);
+        let delivering = match rules.mode {
+            GameMode::Teams => profile
+                .team
+                .and_then(|team| map.team_base(team))
+                .is_some_and(|base| at_drop_off(&base)),
+            GameMode::Delivery => map.drop_offs.iter().any(at_drop_off),
+            GameMode::FreeForAll => false,
+        };
+        if !delivering {
+            continue;
+        }
+
+        let points = inventory.points();
+        inventory.0.clear();
+        score.0 += points;
+        info!("Player {} delivered {points}", player.client_id);
+
+        network.broadcast(&ServerMessage::ScoreUpdate {
+            client_id: player.client_id,
+            score: score.0,
+        });
+        network.broadcast(&inventory.message(player.client_id));
+
+        if let Some(team) = profile.team.filter(|_| rules.mode == GameMode::Teams) {
+            let team_score = team_scores.add(team, points);
+            network.broadcast(&ServerMessage::TeamScore {
+                team,
+                score: team_score,
+            });
+        }
+    }
+}
+
+/// A player whose client drops lets go of everything they were carrying
+fn drop_on_disconnect(
+    mut commands: Commands,
+    map: Res<MapDefinition>,
+    mut collectible_entities: ResMut<CollectibleEntityMap>,
+    mut ids: ResMut<NetworkIds>,
+    mut network: ServerNetwork,
+    mut players: Query<(&Player, &Transform, &mut Inventory), Added<DisconnectedPlayer>>,
+) {
+    for (player, transform, mut inventory) in players.iter_mut() {
+        if inventory.0.is_empty() {
+            continue;
+        }
+
+        let dropped = std::mem::take(&mut inventory.0);
+        let spawned = scatter_items(
+            &mut commands,
+            &mut collectible_entities,
+            &mut ids,
+            &map,
+            transform.translation.truncate(),
+            &dropped,
+        );
+        info!(
+            "Player {} dropped {} items on disconnecting",
+            player.client_id,
+            dropped.len()
+        );
+
+        network.broadcast(&ServerMessage::SpawnCollectibles(spawned));
+        network.broadcast(&inventory.message(player.client_id));
+    }
+}
+
+fn send_inventories_on_connect(
+    mut events: EventReader<ServerEvent>,
+    mut network: ServerNetwork,
+    players: Query<(&Player, &Inventory)>,
+) {
+    for event in events.read() {
+        let ServerEvent::ClientConnected { client_id } = event else {
+            continue;
+        };
+
+        for (player, inventory) in players.iter() {
+            network.send(*client_id, &inventory.message(player.client_id));
+        }
+    }
+}
+
+// === Components and Resources ===
+/// Boxes picked up in team or delivery mode that only score once brought to a drop-off
+#[derive(Component, Default)]
+pub struct Inventory(pub Vec<CollectibleKind>);
+
+impl Inventory {
+    pub fn weight(&self) -> u32 {
+        self.0.iter().map(|kind| kind.weight()).sum()
+    }
+
+    pub fn points(&self) -> u32 {
+        self.0.iter().map(|kind| kind.points()).sum()
+    }
+
+    /// Whether picking this up would stay within [`MAX_CARRY_WEIGHT`]
+    pub fn fits(&self, kind: CollectibleKind) -> bool {
+        self.weight() + kind.weight() <= MAX_CARRY_WEIGHT
+    }
+
+    /// How much of their usual acceleration the carrier has left
+    pub fn acceleration_multiplier(&self) -> f32 {
+        carry_multiplier(self.weight())
+    }
+
+    /// Removes up to `count` items, most recently picked up first
+    pub fn take(&mut self, count: usize) -> Vec<CollectibleKind> {
+        let keep = self.0.len().saturating_sub(count);
+        self.0.split_off(keep)
+    }
+
+    pub fn message(&self, client_id: u64) -> ServerMessage {
+        ServerMessage::InventoryUpdate {
+            client_id,
+            items: self.0.clone(),
+        }
+    }
+}
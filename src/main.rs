@@ -3,28 +3,81 @@ use std::time::Duration;
 use bevy::ecs::component::Component;
 use renet2::{ChannelConfig, ConnectionConfig, SendType};
 
+mod abilities;
+mod admin;
+mod anticheat;
+mod bumps;
+mod camera;
+mod chat;
+mod chat_box;
 mod client;
+mod controls;
+mod cooldowns;
 #[cfg(feature = "dev")]
 mod dev_tools;
+mod drones;
+mod effects;
+mod flood;
+mod hud;
+mod inspect;
+mod inventory;
+mod map;
+mod minimap;
+mod powerups;
+mod prediction;
+mod projectiles;
 mod protocol;
+mod recording;
+mod replay;
+mod rounds;
+mod scoreboard;
+mod screens;
 mod server;
+mod shooting;
+mod spawner;
+mod spectator_camera;
+mod spectators;
+mod teams;
 
 use protocol::*;
 
 const MAX_ACCELERATION: f32 = 1000.0;
+/// Linear damping on player bodies, mirrored by client-side prediction
+const PLAYER_LINEAR_DAMPING: f32 = 5.0;
 // const MAX_VELOCITY: f32 = 200.0;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     match args.get(1).map(|s| s.as_str()) {
-        Some("server") => server::run(),
-        Some("client") => client::run(),
+        Some("server") => server::run(&args[2..]),
+        Some("client") => client::run(&args[2..]),
+        Some("replay") => replay::run(&args[2..]),
+        Some("inspect") => inspect::run(&args[2..]),
         _ => {
-            eprintln!("Usage: cargo run --bin main -- [server|client]");
+            eprintln!(
+                "Usage: cargo run --bin main -- \
+                 [server [--record <file>] [--inspect]|client [--connect <addr>] [--name <name>] [--inspect]|replay <file>|inspect <file>]"
+            );
         }
     }
 }
 
+/// Returns the value following `flag` in a list of command line arguments
+pub fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|s| s.as_str())
+}
+
+/// Parses a duration given in seconds, or `None` if it isn't a finite, non-negative number
+pub fn parse_secs(value: &str) -> Option<Duration> {
+    value
+        .parse::<f32>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f32(secs).ok())
+}
+
 pub fn connection_config() -> ConnectionConfig {
     let channel = ChannelConfig {
         channel_id: 0,
@@ -33,10 +86,19 @@ pub fn connection_config() -> ConnectionConfig {
             resend_time: Duration::from_millis(200),
         },
     };
+    let action_channel = ChannelConfig {
+        channel_id: ClientChannel::Action.into(),
+        ..channel.clone()
+    };
+    let chat_channel = ChannelConfig {
+        channel_id: ClientChannel::Chat.into(),
+        max_memory_usage_bytes: 64 * 1024,
+        ..channel.clone()
+    };
 
     ConnectionConfig {
         available_bytes_per_tick: 1024 * 1024,
-        client_channels_config: vec![channel.clone()],
+        client_channels_config: vec![channel.clone(), action_channel, chat_channel],
         server_channels_config: vec![channel],
     }
 }
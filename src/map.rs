@@ -0,0 +1,268 @@
+use crate::protocol::{CollectibleKind, PowerUp, Team};
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{io, path::Path};
+
+pub const DEFAULT_MAP: &str = "assets/maps/arena.ron";
+
+/// How thick the walls around the arena bounds are
+const BOUNDARY_THICKNESS: f32 = 20.0;
+const WALL_COLOR: Color = Color::srgb(0.35, 0.35, 0.4);
+const OBSTACLE_COLOR: Color = Color::srgb(0.25, 0.3, 0.25);
+const FLOOR_COLOR: Color = Color::srgb(0.08, 0.08, 0.1);
+const DROP_OFF_COLOR: Color = Color::srgba(1.0, 0.85, 0.2, 0.2);
+
+/// An arena layout, loaded from a RON file by the server and sent to clients as they join
+#[derive(Resource, Serialize, Deserialize, Debug, Clone)]
+pub struct MapDefinition {
+    pub name: String,
+    /// Half the width and height of the playable area, centered on the origin. Walls are
+    /// added around it automatically.
+    pub half_extents: Vec2,
+    #[serde(default)]
+    pub walls: Vec<MapRect>,
+    #[serde(default)]
+    pub obstacles: Vec<MapRect>,
+    /// Areas collectibles can appear in
+    pub spawn_zones: Vec<MapRect>,
+    /// Where players are placed when they join or a round starts
+    #[serde(default)]
+    pub player_spawn: Vec2,
+    #[serde(default)]
+    pub collectibles: CollectibleRules,
+    /// Where each team brings its boxes in team mode
+    #[serde(default)]
+    pub team_bases: Vec<TeamBase>,
+    /// Where anyone can bring their boxes in delivery mode
+    #[serde(default)]
+    pub drop_offs: Vec<MapRect>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct TeamBase {
+    pub team: Team,
+    pub zone: MapRect,
+}
+
+/// How many boxes the spawner keeps in the world and how it places them
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CollectibleRules {
+    pub target_count: usize,
+    /// Closest a new box may appear to another box or a player
+    pub min_spacing: f32,
+    /// Delay before a collected box is replaced
+    pub respawn_delay_secs: f32,
+    /// Kinds of collectible to spawn, each with a relative weight
+    pub kinds: Vec<(CollectibleKind, u32)>,
+}
+
+impl Default for CollectibleRules {
+    fn default() -> Self {
+        Self {
+            target_count: 3,
+            min_spacing: 60.0,
+            respawn_delay_secs: 1.0,
+            kinds: vec![
+                (CollectibleKind::Points(1), 12),
+                (CollectibleKind::Points(3), 3),
+                (CollectibleKind::PowerUp(PowerUp::SpeedBoost), 1),
+                (CollectibleKind::PowerUp(PowerUp::Shield), 1),
+                (CollectibleKind::PowerUp(PowerUp::Magnet), 1),
+            ],
+        }
+    }
+}
+
+/// An axis-aligned rectangle
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct MapRect {
+    pub center: Vec2,
+    pub size: Vec2,
+}
+
+impl MapRect {
+    fn validate(&self) -> Result<(), String> {
+        if !self.center.is_finite() {
+            return Err(format!("center must be finite, got {}", self.center));
+        }
+        if !self.size.is_finite() || self.size.min_element() <= 0.0 {
+            return Err(format!("size must be positive, got {}", self.size));
+        }
+        Ok(())
+    }
+
+    /// Whether `point` is inside the rectangle grown by `margin` on every side
+    pub fn contains(&self, point: Vec2, margin: f32) -> bool {
+        let offset = (point - self.center).abs();
+        let half = self.size / 2.0 + margin;
+        offset.x <= half.x && offset.y <= half.y
+    }
+
+    pub fn random_point(&self, rng: &mut impl Rng) -> Vec2 {
+        let half = self.size / 2.0;
+        Vec2::new(
+            rng.random_range(-half.x..=half.x),
+            rng.random_range(-half.y..=half.y),
+        ) + self.center
+    }
+}
+
+impl MapDefinition {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let map: Self =
+            ron::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        map.validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(map)
+    }
+
+    /// Catches values that would otherwise only fail mid-game, such as a negative size or
+    /// respawn delay
+    fn validate(&self) -> Result<(), String> {
+        if !self.half_extents.is_finite() || self.half_extents.min_element() <= 0.0 {
+            return Err(format!(
+                "half_extents must be positive, got {}",
+                self.half_extents
+            ));
+        }
+        if !self.player_spawn.is_finite() {
+            return Err("player_spawn must be a finite position".to_string());
+        }
+        if self.spawn_zones.is_empty() {
+            return Err("map has no spawn zones".to_string());
+        }
+
+        let rects = [
+            ("walls", &self.walls),
+            ("obstacles", &self.obstacles),
+            ("spawn_zones", &self.spawn_zones),
+            ("drop_offs", &self.drop_offs),
+        ];
+        for (field, list) in rects {
+            for (i, rect) in list.iter().enumerate() {
+                rect.validate().map_err(|e| format!("{field}[{i}]: {e}"))?;
+            }
+        }
+        for (i, base) in self.team_bases.iter().enumerate() {
+            base.zone
+                .validate()
+                .map_err(|e| format!("team_bases[{i}]: {e}"))?;
+        }
+
+        let rules = &self.collectibles;
+        if rules.target_count == 0 {
+            return Err("collectibles.target_count must be at least 1".to_string());
+        }
+        if !rules.min_spacing.is_finite() || rules.min_spacing < 0.0 {
+            return Err(format!(
+                "collectibles.min_spacing must be zero or more, got {}",
+                rules.min_spacing
+            ));
+        }
+        if !rules.respawn_delay_secs.is_finite() || rules.respawn_delay_secs < 0.0 {
+            return Err(format!(
+                "collectibles.respawn_delay_secs must be zero or more, got {}",
+                rules.respawn_delay_secs
+            ));
+        }
+        if rules.kinds.iter().all(|(_, weight)| *weight == 0) {
+            return Err("collectibles.kinds needs at least one kind with a weight".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// The map's own walls plus the four that close off its bounds
+    pub fn all_walls(&self) -> Vec<MapRect> {
+        let half = self.half_extents;
+        let t = BOUNDARY_THICKNESS;
+        let horizontal = Vec2::new((half.x + t) * 2.0, t);
+        let vertical = Vec2::new(t, (half.y + t) * 2.0);
+
+        let mut walls = vec![
+            MapRect {
+                center: Vec2::new(0.0, half.y + t / 2.0),
+                size: horizontal,
+            },
+            MapRect {
+                center: Vec2::new(0.0, -half.y - t / 2.0),
+                size: horizontal,
+            },
+            MapRect {
+                center: Vec2::new(half.x + t / 2.0, 0.0),
+                size: vertical,
+            },
+            MapRect {
+                center: Vec2::new(-half.x - t / 2.0, 0.0),
+                size: vertical,
+            },
+        ];
+        walls.extend(self.walls.iter().copied());
+        walls
+    }
+
+    /// Every solid piece of the level, walls first
+    pub fn solids(&self) -> impl Iterator<Item = MapRect> {
+        self.all_walls()
+            .into_iter()
+            .chain(self.obstacles.iter().copied())
+    }
+
+    pub fn team_base(&self, team: Team) -> Option<MapRect> {
+        self.team_bases
+            .iter()
+            .find(|base| base.team == team)
+            .map(|base| base.zone)
+    }
+
+    /// A random point inside one of the spawn zones
+    pub fn random_spawn_point(&self, rng: &mut impl Rng) -> Vec2 {
+        let zone = &self.spawn_zones[rng.random_range(0..self.spawn_zones.len())];
+        zone.random_point(rng)
+    }
+
+    /// Sprites for drawing the level; the floor sits behind everything else
+    pub fn sprites(&self) -> Vec<(Sprite, Transform)> {
+        let rect_sprite = |rect: &MapRect, color: Color, z: f32| {
+            (
+                Sprite {
+                    color,
+                    custom_size: Some(rect.size),
+                    ..default()
+                },
+                Transform::from_translation(rect.center.extend(z)),
+            )
+        };
+
+        let floor = MapRect {
+            center: Vec2::ZERO,
+            size: self.half_extents * 2.0,
+        };
+
+        std::iter::once(rect_sprite(&floor, FLOOR_COLOR, -10.0))
+            .chain(
+                self.all_walls()
+                    .iter()
+                    .map(|wall| rect_sprite(wall, WALL_COLOR, -1.0)),
+            )
+            .chain(
+                self.obstacles
+                    .iter()
+                    .map(|obstacle| rect_sprite(obstacle, OBSTACLE_COLOR, -1.0)),
+            )
+            .chain(
+                self.team_bases
+                    .iter()
+                    .map(|base| rect_sprite(&base.zone, base.team.color().with_alpha(0.2), -5.0)),
+            )
+            .chain(
+                self.drop_offs
+                    .iter()
+                    .map(|zone| rect_sprite(zone, DROP_OFF_COLOR, -5.0)),
+            )
+            .collect()
+    }
+}
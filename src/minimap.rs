@@ -0,0 +1,246 @@
+use crate::{
+    BoxCollectable,
+    chat_box::typing,
+    client::{Player, RemoteDrone, RemotePlayer},
+    map::{MapDefinition, MapRect},
+    screens::ClientState,
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+
+/// Length of the minimap's longer side, in pixels
+const MINIMAP_SIZE: f32 = 180.0;
+
+const BACKGROUND_COLOR: Color = Color::srgba(0.05, 0.05, 0.08, 0.8);
+const WALL_COLOR: Color = Color::srgb(0.5, 0.5, 0.55);
+const OBSTACLE_COLOR: Color = Color::srgb(0.35, 0.42, 0.35);
+const DROP_OFF_COLOR: Color = Color::srgba(1.0, 0.85, 0.2, 0.35);
+const LOCAL_PLAYER_COLOR: Color = Color::WHITE;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(ClientState::InGame), spawn_minimap)
+        .add_systems(
+            Update,
+            (
+                toggle_minimap.run_if(not(typing)),
+                draw_minimap_layout.run_if(resource_exists_and_changed::<MapDefinition>),
+                update_minimap_markers,
+            )
+                .chain()
+                .run_if(in_state(ClientState::InGame)),
+        );
+}
+
+// === Minimap ===
+fn spawn_minimap(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(ClientState::InGame),
+        MinimapPanel,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(8.0),
+            width: Val::Px(MINIMAP_SIZE),
+            height: Val::Px(MINIMAP_SIZE),
+            overflow: Overflow::clip(),
+            ..default()
+        },
+        BackgroundColor(BACKGROUND_COLOR),
+        // The arena bounds; an outline takes no room from the map inside
+        Outline::new(Val::Px(2.0), Val::ZERO, WALL_COLOR),
+        MinimapScale::default(),
+    ));
+}
+
+/// M shows and hides the minimap
+fn toggle_minimap(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut panel: Query<&mut Visibility, With<MinimapPanel>>,
+) {
+    if !keys.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+    for mut visibility in panel.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+/// Fits the panel to the map's shape and draws its walls, obstacles and zones
+fn draw_minimap_layout(
+    mut commands: Commands,
+    map: Res<MapDefinition>,
+    mut panel: Query<(Entity, &mut Node, &mut MinimapScale), With<MinimapPanel>>,
+    layout: Query<Entity, With<MinimapLayout>>,
+) {
+    let Ok((panel, mut node, mut scale)) = panel.single_mut() else {
+        return;
+    };
+
+    *scale = MinimapScale::fit(map.half_extents);
+    let size = map.half_extents * 2.0 * scale.pixels_per_unit;
+    node.width = Val::Px(size.x);
+    node.height = Val::Px(size.y);
+
+    for entity in layout.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let zones = map
+        .team_bases
+        .iter()
+        .map(|base| (base.zone, base.team.color().with_alpha(0.35)))
+        .chain(map.drop_offs.iter().map(|zone| (*zone, DROP_OFF_COLOR)));
+    let solids = map.walls.iter().map(|wall| (*wall, WALL_COLOR)).chain(
+        map.obstacles
+            .iter()
+            .map(|obstacle| (*obstacle, OBSTACLE_COLOR)),
+    );
+
+    commands.entity(panel).with_children(|parent| {
+        for (rect, color) in zones.chain(solids) {
+            parent.spawn((
+                MinimapLayout,
+                scale.rect_node(&rect),
+                BackgroundColor(color),
+            ));
+        }
+    });
+}
+
+/// Keeps a dot on the minimap for every player, drone and collectible we know about. Only
+/// what the server has sent us is shown, so the minimap never reveals more than the world.
+#[allow(
+    clippy::type_complexity,
+    reason = "one query covers every kind of entity shown on the minimap"
+)]
+fn update_minimap_markers(
+    mut commands: Commands,
+    panel: Query<(Entity, &MinimapScale), With<MinimapPanel>>,
+    tracked: Query<
+        (
+            Entity,
+            &Transform,
+            &Sprite,
+            Has<Player>,
+            Has<RemotePlayer>,
+            Has<RemoteDrone>,
+        ),
+        Or<(With<Player>, With<RemoteDrone>, With<BoxCollectable>)>,
+    >,
+    mut markers: Query<(Entity, &MinimapMarker, &mut Node, &mut BackgroundColor)>,
+) {
+    let Ok((panel, scale)) = panel.single() else {
+        return;
+    };
+
+    let mut existing: HashMap<Entity, Entity> = HashMap::default();
+    for (marker, target, ..) in markers.iter() {
+        if tracked.contains(target.0) {
+            existing.insert(target.0, marker);
+        } else {
+            commands.entity(marker).despawn();
+        }
+    }
+
+    for (entity, transform, sprite, is_player, is_remote, is_drone) in tracked.iter() {
+        let (size, color) = match (is_player, is_remote, is_drone) {
+            (true, false, _) => (8.0, LOCAL_PLAYER_COLOR),
+            (true, true, _) => (6.0, sprite.color),
+            (_, _, true) => (5.0, sprite.color),
+            _ => (4.0, sprite.color),
+        };
+        let node = scale.dot_node(transform.translation.truncate(), size);
+
+        match existing
+            .get(&entity)
+            .and_then(|marker| markers.get_mut(*marker).ok())
+        {
+            Some((_, _, mut marker_node, mut background)) => {
+                *marker_node = node;
+                background.0 = color;
+            }
+            None => {
+                let marker = commands
+                    .spawn((
+                        MinimapMarker(entity),
+                        node,
+                        BackgroundColor(color),
+                        // Players draw over boxes
+                        ZIndex(if is_player { 2 } else { 1 }),
+                    ))
+                    .id();
+                commands.entity(panel).add_child(marker);
+            }
+        }
+    }
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+struct MinimapPanel;
+
+/// Walls, obstacles and zones drawn on the minimap, redrawn when the map changes
+#[derive(Component)]
+struct MinimapLayout;
+
+/// A dot standing in for a player, drone or collectible
+#[derive(Component)]
+struct MinimapMarker(Entity);
+
+/// How world positions map onto the panel
+#[derive(Component)]
+struct MinimapScale {
+    half_extents: Vec2,
+    pixels_per_unit: f32,
+}
+
+impl Default for MinimapScale {
+    fn default() -> Self {
+        Self::fit(Vec2::splat(MINIMAP_SIZE / 2.0))
+    }
+}
+
+impl MinimapScale {
+    /// Fits the map's longer side to [`MINIMAP_SIZE`]
+    fn fit(half_extents: Vec2) -> Self {
+        Self {
+            half_extents,
+            pixels_per_unit: MINIMAP_SIZE / (half_extents.max_element() * 2.0).max(1.0),
+        }
+    }
+
+    /// Offset from the panel's top left corner, with y pointing down
+    fn to_panel(&self, position: Vec2) -> Vec2 {
+        Vec2::new(
+            position.x + self.half_extents.x,
+            self.half_extents.y - position.y,
+        ) * self.pixels_per_unit
+    }
+
+    fn rect_node(&self, rect: &MapRect) -> Node {
+        let top_left = self.to_panel(rect.center + Vec2::new(-rect.size.x, rect.size.y) / 2.0);
+        let size = rect.size * self.pixels_per_unit;
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(top_left.x),
+            top: Val::Px(top_left.y),
+            width: Val::Px(size.x.max(1.0)),
+            height: Val::Px(size.y.max(1.0)),
+            ..default()
+        }
+    }
+
+    fn dot_node(&self, position: Vec2, size: f32) -> Node {
+        let center = self.to_panel(position);
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(center.x - size / 2.0),
+            top: Val::Px(center.y - size / 2.0),
+            width: Val::Px(size),
+            height: Val::Px(size),
+            ..default()
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use crate::{
+    BoxCollectable, ServerMessage,
+    protocol::{CollectiblePosition, PowerUp},
+    server::{CollectibleId, Player, ServerNetwork},
+};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Dominance;
+
+/// Acceleration multiplier while a speed boost is active
+pub const SPEED_BOOST_MULTIPLIER: f32 = 1.6;
+
+/// How close a collectible has to be for a magnet to grab it
+const MAGNET_RADIUS: f32 = 220.0;
+
+/// Pixels per second a magnet pulls collectibles in at
+const MAGNET_PULL_SPEED: f32 = 260.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, (expire_effects, pull_collectibles));
+}
+
+/// Starts (or restarts) a power-up's effect on a player and tells everyone
+pub fn apply_power_up(
+    commands: &mut Commands,
+    network: &mut ServerNetwork,
+    entity: Entity,
+    client_id: u64,
+    effects: &mut ActiveEffects,
+    power_up: PowerUp,
+) {
+    let duration = power_up_duration(power_up);
+    effects.start(power_up, duration);
+
+    if power_up == PowerUp::Shield {
+        // Shielded players push others around without being pushed back
+        commands.entity(entity).insert(Dominance::group(1));
+    }
+
+    network.broadcast(&ServerMessage::EffectStarted {
+        client_id,
+        power_up,
+        remaining_secs: duration,
+    });
+}
+
+fn power_up_duration(power_up: PowerUp) -> f32 {
+    match power_up {
+        PowerUp::SpeedBoost => 6.0,
+        PowerUp::Shield => 8.0,
+        PowerUp::Magnet => 8.0,
+    }
+}
+
+// === Effects ===
+fn expire_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut network: ServerNetwork,
+    mut players: Query<(Entity, &Player, &mut ActiveEffects)>,
+) {
+    for (entity, player, mut effects) in players.iter_mut() {
+        for power_up in effects.tick(time.delta()) {
+            if power_up == PowerUp::Shield {
+                commands.entity(entity).remove::<Dominance>();
+            }
+
+            network.broadcast(&ServerMessage::EffectEnded {
+                client_id: player.client_id,
+                power_up,
+            });
+        }
+    }
+}
+
+#[allow(
+    clippy::type_complexity,
+    reason = "the filters keep player and box transforms apart"
+)]
+fn pull_collectibles(
+    time: Res<Time>,
+    mut network: ServerNetwork,
+    players: Query<(&Transform, &ActiveEffects), With<Player>>,
+    mut boxes: Query<(&CollectibleId, &mut Transform), (With<BoxCollectable>, Without<Player>)>,
+) {
+    let magnets: Vec<Vec3> = players
+        .iter()
+        .filter(|(_, effects)| effects.has(PowerUp::Magnet))
+        .map(|(transform, _)| transform.translation)
+        .collect();
+    if magnets.is_empty() {
+        return;
+    }
+
+    let step = MAGNET_PULL_SPEED * time.delta_secs();
+    let mut moved = Vec::new();
+
+    for (id, mut transform) in boxes.iter_mut() {
+        // Only the closest magnet in range gets to pull
+        let Some(magnet) = magnets
+            .iter()
+            .filter(|magnet| magnet.distance(transform.translation) <= MAGNET_RADIUS)
+            .min_by(|a, b| {
+                a.distance(transform.translation)
+                    .total_cmp(&b.distance(transform.translation))
+            })
+        else {
+            continue;
+        };
+
+        transform.translation = transform.translation.move_towards(*magnet, step);
+        moved.push(CollectiblePosition {
+            id: id.0,
+            position: transform.translation,
+        });
+    }
+
+    if !moved.is_empty() {
+        network.broadcast(&ServerMessage::CollectiblePositions(moved));
+    }
+}
+
+// === Components and Resources ===
+/// Power-ups currently affecting a player, with the time each has left
+#[derive(Component, Default)]
+pub struct ActiveEffects(pub Vec<(PowerUp, Timer)>);
+
+impl ActiveEffects {
+    pub fn has(&self, power_up: PowerUp) -> bool {
+        self.0.iter().any(|(active, _)| *active == power_up)
+    }
+
+    pub fn acceleration_multiplier(&self) -> f32 {
+        if self.has(PowerUp::SpeedBoost) {
+            SPEED_BOOST_MULTIPLIER
+        } else {
+            1.0
+        }
+    }
+
+    fn start(&mut self, power_up: PowerUp, secs: f32) {
+        self.0.retain(|(active, _)| *active != power_up);
+        self.0
+            .push((power_up, Timer::from_seconds(secs, TimerMode::Once)));
+    }
+
+    /// Advances every timer and returns the power-ups that just ran out
+    fn tick(&mut self, delta: std::time::Duration) -> Vec<PowerUp> {
+        let mut expired = Vec::new();
+        self.0.retain_mut(|(power_up, timer)| {
+            if timer.tick(delta).finished() {
+                expired.push(*power_up);
+                false
+            } else {
+                true
+            }
+        });
+        expired
+    }
+}
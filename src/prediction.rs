@@ -0,0 +1,63 @@
+use crate::{PLAYER_LINEAR_DAMPING, screens::ClientState};
+use bevy::prelude::*;
+
+/// Errors bigger than this are snapped rather than smoothed, e.g. after a teleport
+const SNAP_DISTANCE: f32 = 120.0;
+
+/// Fraction of the remaining error corrected per second
+const CORRECTION_RATE: f32 = 10.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        integrate_motion.run_if(in_state(ClientState::InGame)),
+    );
+}
+
+// === Prediction ===
+/// Moves every player along its predicted velocity, then eases it towards where the server
+/// says it should be
+fn integrate_motion(time: Res<Time>, mut players: Query<(&mut Transform, &mut NetMotion)>) {
+    let dt = time.delta_secs();
+
+    for (mut transform, mut motion) in players.iter_mut() {
+        let Some(mut server_position) = motion.server_position else {
+            continue;
+        };
+
+        // Same damping Rapier applies on the server
+        let damping = 1.0 / (1.0 + dt * PLAYER_LINEAR_DAMPING);
+        motion.velocity *= damping;
+        motion.server_velocity *= damping;
+
+        server_position += motion.server_velocity.extend(0.0) * dt;
+        motion.server_position = Some(server_position);
+        transform.translation += motion.velocity.extend(0.0) * dt;
+
+        let error = server_position - transform.translation;
+        if error.length() > SNAP_DISTANCE {
+            transform.translation = server_position;
+        } else {
+            transform.translation += error * (CORRECTION_RATE * dt).min(1.0);
+        }
+    }
+}
+
+// === Components and Resources ===
+/// Client-side view of a player's (or drone's) motion: our own prediction plus the last
+/// server state, extrapolated forward
+#[derive(Component, Default)]
+pub struct NetMotion {
+    pub velocity: Vec2,
+    server_position: Option<Vec3>,
+    server_velocity: Vec2,
+}
+
+impl NetMotion {
+    /// Takes in an authoritative update; knockback and bounces arrive through its velocity
+    pub fn correct(&mut self, position: Vec3, velocity: Vec2) {
+        self.server_position = Some(position);
+        self.server_velocity = velocity;
+        self.velocity = velocity;
+    }
+}
@@ -0,0 +1,392 @@
+use crate::{
+    ServerMessage,
+    inventory::{Inventory, scatter_items},
+    map::MapDefinition,
+    powerups::ActiveEffects,
+    protocol::{
+        CollectibleKind, FIRE_COOLDOWN_SECS, PROJECTILE_RADIUS, PlayerProfile, PowerUp,
+        ProjectileInfo,
+    },
+    rounds::{MatchRules, MatchState},
+    server::{CollectibleEntityMap, DisconnectedPlayer, NetworkIds, Player, Score, ServerNetwork},
+};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use std::collections::VecDeque;
+
+const PROJECTILE_SPEED: f32 = 900.0;
+
+/// Seconds a projectile flies before fizzling out
+const PROJECTILE_LIFETIME_SECS: f32 = 1.2;
+
+/// Half the side of a player's square, matching their collider
+const PLAYER_HALF_SIZE: f32 = 15.0;
+
+/// Furthest back in time a shot can be checked against. Anything older is treated as this old,
+/// so very laggy clients have to lead their targets a little.
+const MAX_REWIND_SECS: f32 = 0.3;
+
+/// Seconds a hit player can't move or act
+const HIT_STUN_SECS: f32 = 0.6;
+
+/// Points, or carried boxes, knocked loose from a hit player and scattered around them
+const HIT_DROP: u32 = 2;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PositionHistory>().add_systems(
+        Update,
+        (tick_timers, hit_players, hit_walls, expire_projectiles).chain(),
+    );
+}
+
+/// Spawns a projectile for a shot, remembering how far behind the shooter's view of the
+/// world was so hits can be checked against it
+#[allow(
+    clippy::too_many_arguments,
+    reason = "called from message handling, which already holds each of these"
+)]
+pub fn fire(
+    commands: &mut Commands,
+    network: &mut ServerNetwork,
+    ids: &mut NetworkIds,
+    history: &PositionHistory,
+    now: f32,
+    shooter: Entity,
+    client_id: u64,
+    origin: Vec3,
+    direction: Vec2,
+    frame: u64,
+) {
+    let direction = direction.normalize_or_zero();
+    if direction == Vec2::ZERO {
+        return;
+    }
+
+    // Start just outside the shooter so it doesn't hit them on the way out
+    let position = origin + (direction * (PLAYER_HALF_SIZE * 2.0 + PROJECTILE_RADIUS)).extend(0.0);
+    let velocity = direction * PROJECTILE_SPEED;
+    let id = ids.allocate();
+
+    commands.spawn((
+        Projectile {
+            id,
+            owner: shooter,
+            owner_id: client_id,
+            rewind_secs: history.rewind_secs(frame, now),
+            lifetime: Timer::from_seconds(PROJECTILE_LIFETIME_SECS, TimerMode::Once),
+        },
+        RigidBody::Dynamic,
+        Collider::ball(PROJECTILE_RADIUS),
+        Sensor,
+        ActiveEvents::COLLISION_EVENTS,
+        Velocity::linear(velocity),
+        Transform::from_translation(position),
+        GlobalTransform::default(),
+    ));
+    commands
+        .entity(shooter)
+        .insert(Reloading(Timer::from_seconds(
+            FIRE_COOLDOWN_SECS,
+            TimerMode::Once,
+        )));
+
+    network.broadcast(&ServerMessage::ProjectileSpawned(ProjectileInfo {
+        id,
+        owner: client_id,
+        position,
+        velocity,
+    }));
+}
+
+// === Projectiles ===
+fn tick_timers(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut reloading: Query<(Entity, &mut Reloading)>,
+    mut stunned: Query<(Entity, &mut Stunned)>,
+    mut projectiles: Query<&mut Projectile>,
+) {
+    for (entity, mut reloading) in reloading.iter_mut() {
+        if reloading.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Reloading>();
+        }
+    }
+    for (entity, mut stunned) in stunned.iter_mut() {
+        if stunned.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Stunned>();
+        }
+    }
+    for mut projectile in projectiles.iter_mut() {
+        projectile.lifetime.tick(time.delta());
+    }
+}
+
+/// Checks each projectile against where players were when its shooter fired, rather than
+/// where they are now. Teammates are passed through.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "hits are checked against position history, teams and shields"
+)]
+fn hit_players(
+    mut commands: Commands,
+    time: Res<Time>,
+    history: Res<PositionHistory>,
+    map: Res<MapDefinition>,
+    match_rules: Res<MatchRules>,
+    match_state: Res<MatchState>,
+    mut collectible_entities: ResMut<CollectibleEntityMap>,
+    mut ids: ResMut<NetworkIds>,
+    mut network: ServerNetwork,
+    projectiles: Query<(Entity, &Projectile, &Transform)>,
+    mut players: Query<
+        (
+            &Player,
+            &Transform,
+            &mut Score,
+            &mut Inventory,
+            &ActiveEffects,
+        ),
+        Without<DisconnectedPlayer>,
+    >,
+    profiles: Query<&PlayerProfile>,
+) {
+    let now = time.elapsed_secs();
+
+    for (entity, projectile, transform) in projectiles.iter() {
+        let Some(snapshot) = history.at(now - projectile.rewind_secs) else {
+            continue;
+        };
+
+        let point = transform.translation.truncate();
+        let reach = PLAYER_HALF_SIZE + PROJECTILE_RADIUS;
+        let team = |entity: Entity| profiles.get(entity).ok().and_then(|profile| profile.team);
+        let owner_team = team(projectile.owner);
+        let Some(target) = snapshot.positions.iter().find_map(|(target, position)| {
+            let offset = (point - *position).abs();
+            (*target != projectile.owner
+                && (owner_team.is_none() || team(*target) != owner_team)
+                && offset.x <= reach
+                && offset.y <= reach
+                && players.contains(*target))
+            .then_some(*target)
+        }) else {
+            continue;
+        };
+        let Ok((target_player, target_transform, mut score, mut inventory, effects)) =
+            players.get_mut(target)
+        else {
+            continue;
+        };
+
+        commands.entity(entity).try_despawn();
+        network.broadcast(&ServerMessage::ProjectileDespawned { id: projectile.id });
+
+        // A shield soaks up the shot
+        if effects.has(PowerUp::Shield) {
+            continue;
+        }
+
+        commands
+            .entity(target)
+            .insert(Stunned(Timer::from_seconds(HIT_STUN_SECS, TimerMode::Once)));
+
+        // When boxes are carried it's those that get knocked loose, otherwise the score
+        let items = if !match_state.allows_collecting() {
+            Vec::new()
+        } else if match_rules.carries() {
+            inventory.take(HIT_DROP as usize)
+        } else {
+            let points = HIT_DROP.min(score.0);
+            score.0 -= points;
+            vec![CollectibleKind::Points(1); points as usize]
+        };
+        let dropped = items.len() as u32;
+        if dropped > 0 {
+            network.broadcast(&if match_rules.carries() {
+                inventory.message(target_player.client_id)
+            } else {
+                ServerMessage::ScoreUpdate {
+                    client_id: target_player.client_id,
+                    score: score.0,
+                }
+            });
+
+            let spawned = scatter_items(
+                &mut commands,
+                &mut collectible_entities,
+                &mut ids,
+                &map,
+                target_transform.translation.truncate(),
+                &items,
+            );
+            network.broadcast(&ServerMessage::SpawnCollectibles(spawned));
+        }
+
+        info!(
+            "Player {} hit {} and knocked {dropped} loose",
+            projectile.owner_id, target_player.client_id
+        );
+        network.broadcast(&ServerMessage::PlayerHit {
+            shooter: projectile.owner_id,
+            target: target_player.client_id,
+            stun_secs: HIT_STUN_SECS,
+            dropped,
+        });
+    }
+}
+
+/// Projectiles stop at walls and obstacles; players are handled by `hit_players`
+fn hit_walls(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionEvent>,
+    mut network: ServerNetwork,
+    projectiles: Query<&Projectile>,
+    players: Query<(), With<Player>>,
+) {
+    for event in collisions.read() {
+        let CollisionEvent::Started(a, b, _) = event else {
+            continue;
+        };
+
+        for (entity, other) in [(*a, *b), (*b, *a)] {
+            let Ok(projectile) = projectiles.get(entity) else {
+                continue;
+            };
+            if players.contains(other) || projectiles.contains(other) {
+                continue;
+            }
+
+            commands.entity(entity).try_despawn();
+            network.broadcast(&ServerMessage::ProjectileDespawned { id: projectile.id });
+        }
+    }
+}
+
+fn expire_projectiles(
+    mut commands: Commands,
+    mut network: ServerNetwork,
+    projectiles: Query<(Entity, &Projectile)>,
+) {
+    for (entity, projectile) in projectiles.iter() {
+        if projectile.lifetime.finished() {
+            commands.entity(entity).try_despawn();
+            network.broadcast(&ServerMessage::ProjectileDespawned { id: projectile.id });
+        }
+    }
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+pub struct Projectile {
+    id: u64,
+    owner: Entity,
+    owner_id: u64,
+    /// How far in the past the shooter was seeing other players
+    rewind_secs: f32,
+    lifetime: Timer,
+}
+
+/// Can't fire again until this runs out
+#[derive(Component)]
+pub struct Reloading(Timer);
+
+/// Hit by a projectile; movement and actions are ignored until this runs out
+#[derive(Component)]
+pub struct Stunned(Timer);
+
+pub struct Snapshot {
+    pub tick: u64,
+    /// Elapsed time when the snapshot was sent
+    pub time: f32,
+    pub positions: Vec<(Entity, Vec2)>,
+}
+
+/// Player positions as recently sent to clients, for rewinding hit checks
+#[derive(Resource, Default)]
+pub struct PositionHistory(VecDeque<Snapshot>);
+
+impl PositionHistory {
+    pub fn record(&mut self, snapshot: Snapshot) {
+        let cutoff = snapshot.time - MAX_REWIND_SECS * 2.0;
+        while self.0.front().is_some_and(|oldest| oldest.time < cutoff) {
+            self.0.pop_front();
+        }
+        self.0.push_back(snapshot);
+    }
+
+    /// How long ago the snapshot with this tick was sent, capped at [`MAX_REWIND_SECS`].
+    /// Ticks we never sent count as no rewind if they're in the future, the full amount if not.
+    fn rewind_secs(&self, tick: u64, now: f32) -> f32 {
+        let sent = match self.0.iter().find(|snapshot| snapshot.tick == tick) {
+            Some(snapshot) => snapshot.time,
+            None if self.0.back().is_none_or(|newest| tick > newest.tick) => now,
+            None => now - MAX_REWIND_SECS,
+        };
+        (now - sent).clamp(0.0, MAX_REWIND_SECS)
+    }
+
+    /// The newest snapshot sent at or before `time`
+    fn at(&self, time: f32) -> Option<&Snapshot> {
+        self.0
+            .iter()
+            .rev()
+            .find(|snapshot| snapshot.time <= time)
+            .or(self.0.front())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Snapshots sent every 0.05 seconds, `tick` at `tick / 20` seconds
+    fn history(ticks: std::ops::RangeInclusive<u64>) -> PositionHistory {
+        let mut history = PositionHistory::default();
+        for tick in ticks {
+            history.record(Snapshot {
+                tick,
+                time: tick as f32 / 20.0,
+                positions: vec![(Entity::PLACEHOLDER, Vec2::splat(tick as f32))],
+            });
+        }
+        history
+    }
+
+    #[test]
+    fn old_snapshots_are_dropped() {
+        let history = history(0..=100);
+        let oldest = history.0.front().unwrap();
+        assert!(oldest.time >= 5.0 - MAX_REWIND_SECS * 2.0);
+        assert_eq!(history.0.back().unwrap().tick, 100);
+    }
+
+    #[test]
+    fn rewind_goes_back_to_when_the_tick_was_sent() {
+        let history = history(0..=20);
+        let now = 1.0;
+        assert!((history.rewind_secs(18, now) - 0.1).abs() < 1e-4);
+        assert_eq!(history.rewind_secs(20, now), 0.0);
+    }
+
+    #[test]
+    fn rewind_is_capped_and_unknown_ticks_are_handled() {
+        let history = history(0..=20);
+        let now = 1.0;
+        assert_eq!(history.rewind_secs(2, now), MAX_REWIND_SECS);
+        assert_eq!(history.rewind_secs(25, now), 0.0);
+
+        let gap = PositionHistory(history.0.into_iter().filter(|s| s.tick != 19).collect());
+        assert_eq!(gap.rewind_secs(19, now), MAX_REWIND_SECS);
+    }
+
+    #[test]
+    fn at_finds_the_newest_snapshot_not_after_the_time() {
+        let history = history(10..=20);
+        assert_eq!(history.at(0.82).unwrap().tick, 16);
+        assert_eq!(history.at(0.85).unwrap().tick, 17);
+        assert_eq!(history.at(5.0).unwrap().tick, 20);
+        // Earlier than anything kept falls back to the oldest
+        assert_eq!(history.at(0.0).unwrap().tick, 10);
+        assert!(PositionHistory::default().at(1.0).is_none());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::map::MapDefinition;
 use bevy::prelude::*;
+use renet2_netcode::NETCODE_USER_DATA_BYTES;
 use serde::{Deserialize, Serialize};
 
 pub const SERVER_HOST: &'static str = "0.0.0.0:5000";
@@ -6,8 +8,51 @@ pub const SERVER_HOST: &'static str = "0.0.0.0:5000";
 /// Unique protocol ID to identify your game
 pub const PROTOCOL_ID: u64 = 0x12345678;
 
+/// How close a player has to be to a collectible to pick it up
+pub const COLLECT_RADIUS: f32 = 40.0;
+
+/// Most weight a player can carry at once in the modes where boxes are delivered
+pub const MAX_CARRY_WEIGHT: u32 = 10;
+
+/// Acceleration left to a player carrying `weight`, down to half when fully loaded
+pub fn carry_multiplier(weight: u32) -> f32 {
+    1.0 - 0.5 * weight.min(MAX_CARRY_WEIGHT) as f32 / MAX_CARRY_WEIGHT as f32
+}
+
+/// Seconds of movement each `MoveInput` covers. Clients send input at this fixed rate however
+/// fast they render, so their traffic doesn't grow with the monitor's refresh rate.
+pub const INPUT_INTERVAL_SECS: f32 = 1.0 / 60.0;
+
+/// Seconds between shots
+pub const FIRE_COOLDOWN_SECS: f32 = 0.4;
+
+pub const PROJECTILE_RADIUS: f32 = 5.0;
+
+/// Longest display name the server will accept, in characters
+pub const MAX_NAME_LEN: usize = 16;
+
+/// Longest chat message the server will pass on, in characters
+pub const MAX_CHAT_LEN: usize = 200;
+
+/// Most bytes a client message may decode from: a chat message of [`MAX_CHAT_LEN`]
+/// four-byte characters plus room for its length prefix and variant tag. Client packets are
+/// untrusted, so this stops a forged length prefix from making the server allocate gigabytes.
+pub const MAX_CLIENT_MESSAGE_BYTES: usize = MAX_CHAT_LEN * 4 + 16;
+
+/// Colors a player can pick from; sent as an index so the server can validate it
+pub const PLAYER_COLORS: [Color; 8] = [
+    Color::srgb(0.0, 0.0, 1.0),
+    Color::srgb(0.8, 0.2, 1.0),
+    Color::srgb(1.0, 0.3, 0.3),
+    Color::srgb(0.2, 0.8, 0.3),
+    Color::srgb(1.0, 0.6, 0.1),
+    Color::srgb(0.2, 0.9, 0.9),
+    Color::srgb(1.0, 0.5, 0.8),
+    Color::srgb(0.9, 0.9, 0.9),
+];
+
 /// Enum describing messages the client can send to the server
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ClientMessage {
     MoveInput {
         direction: Vec2,
@@ -17,10 +62,27 @@ pub enum ClientMessage {
     AttemptCollect {
         id: u64,
     },
+    /// Sent on the action channel
+    UseAbility {
+        ability: Ability,
+        direction: Vec2,
+    },
+    /// Sent on the action channel
+    Fire {
+        direction: Vec2,
+        /// Tick of the newest player positions the client had when it fired, so the server
+        /// can check hits against what the shooter saw
+        frame: u64,
+    },
+    /// Sent on the chat channel. Slash commands such as `/w` and `/name` are parsed by the
+    /// server.
+    Chat {
+        text: String,
+    },
 }
 
 /// Enum describing messages the server can send to clients
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ServerMessage {
     /// Sends all collectibles at login
     SpawnCollectibles(Vec<CollectibleInfo>),
@@ -30,11 +92,15 @@ pub enum ServerMessage {
         id: u64,
     },
 
-    /// Informs all clients of player movements
-    PlayerPositions(Vec<PositionData>),
+    /// Informs all clients of player movements, stamped with the server tick
+    PlayerPositions {
+        tick: u64,
+        players: Vec<PositionData>,
+    },
 
     SpawnRemotePlayer {
         client_id: u64,
+        profile: PlayerProfile,
     },
 
     DespawnPlayer {
@@ -45,20 +111,402 @@ pub enum ServerMessage {
     AssignClientId {
         client_id: u64,
     },
+
+    /// Token the client can present on reconnect to resume its player
+    SessionToken {
+        token: u64,
+    },
+
+    /// A player's total number of collected boxes
+    ScoreUpdate {
+        client_id: u64,
+        score: u32,
+    },
+
+    /// The arena layout, sent before anything else on connect
+    MapData(MapDefinition),
+
+    /// Sent whenever the match changes phase, and to clients as they join
+    MatchState {
+        phase: MatchPhase,
+        round: u32,
+        /// Seconds until the phase ends, if it is timed
+        remaining_secs: Option<f32>,
+    },
+
+    /// Final standings of a round, highest score first
+    RoundResults {
+        round: u32,
+        winner: Option<u64>,
+        /// Set instead of `winner` in team mode
+        winning_team: Option<Team>,
+        standings: Vec<RoundStanding>,
+    },
+
+    /// A team's delivered total; sent to clients as they join
+    TeamScore {
+        team: Team,
+        score: u32,
+    },
+
+    /// What a player is carrying and has yet to deliver
+    InventoryUpdate {
+        client_id: u64,
+        items: Vec<CollectibleKind>,
+    },
+
+    /// Collectibles that moved, e.g. pulled in by a magnet
+    CollectiblePositions(Vec<CollectiblePosition>),
+
+    /// A player picked up a power-up; sent again to clients as they join
+    EffectStarted {
+        client_id: u64,
+        power_up: PowerUp,
+        remaining_secs: f32,
+    },
+
+    EffectEnded {
+        client_id: u64,
+        power_up: PowerUp,
+    },
+
+    /// Time left before the receiving client can use an ability again
+    AbilityCooldown {
+        ability: Ability,
+        remaining_secs: f32,
+    },
+
+    /// Projectiles fly in a straight line until despawned, so this is only sent once
+    ProjectileSpawned(ProjectileInfo),
+
+    ProjectileDespawned {
+        id: u64,
+    },
+
+    /// A projectile hit a player, stunning them and knocking some boxes loose
+    PlayerHit {
+        shooter: u64,
+        target: u64,
+        stun_secs: f32,
+        dropped: u32,
+    },
+
+    /// Server-controlled drones; sent to clients as they join
+    SpawnDrones(Vec<DroneData>),
+
+    /// Informs all clients of drone movements
+    DronePositions(Vec<DroneData>),
+
+    /// A chat line, only sent to the players the scope covers
+    Chat {
+        scope: ChatScope,
+        from: String,
+        text: String,
+    },
+
+    /// A player changed their name with `/name`
+    PlayerRenamed {
+        client_id: u64,
+        name: String,
+    },
+
+    /// Sent just before the server disconnects this client, saying why
+    Kicked {
+        reason: String,
+    },
+}
+
+impl ClientMessage {
+    /// Decodes a message from a client, refusing any that would take more than
+    /// [`MAX_CLIENT_MESSAGE_BYTES`]
+    pub fn decode(bytes: &[u8]) -> Result<Self, bincode::error::DecodeError> {
+        let config = bincode::config::standard().with_limit::<MAX_CLIENT_MESSAGE_BYTES>();
+        bincode::serde::decode_from_slice(bytes, config).map(|(msg, _)| msg)
+    }
+
+    /// Variant name, used to filter messages in the inspector
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClientMessage::MoveInput { .. } => "MoveInput",
+            ClientMessage::AttemptCollect { .. } => "AttemptCollect",
+            ClientMessage::UseAbility { .. } => "UseAbility",
+            ClientMessage::Fire { .. } => "Fire",
+            ClientMessage::Chat { .. } => "Chat",
+        }
+    }
+
+    /// Discrete actions get their own channel so they never queue behind movement
+    pub fn channel(&self) -> ClientChannel {
+        match self {
+            ClientMessage::MoveInput { .. } | ClientMessage::AttemptCollect { .. } => {
+                ClientChannel::Input
+            }
+            ClientMessage::UseAbility { .. } | ClientMessage::Fire { .. } => ClientChannel::Action,
+            ClientMessage::Chat { .. } => ClientChannel::Chat,
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl ServerMessage {
+    /// Variant name, used to filter messages in the inspector
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ServerMessage::SpawnCollectibles(_) => "SpawnCollectibles",
+            ServerMessage::DespawnCollectible { .. } => "DespawnCollectible",
+            ServerMessage::PlayerPositions { .. } => "PlayerPositions",
+            ServerMessage::SpawnRemotePlayer { .. } => "SpawnRemotePlayer",
+            ServerMessage::DespawnPlayer { .. } => "DespawnPlayer",
+            ServerMessage::AssignClientId { .. } => "AssignClientId",
+            ServerMessage::SessionToken { .. } => "SessionToken",
+            ServerMessage::ScoreUpdate { .. } => "ScoreUpdate",
+            ServerMessage::MapData(_) => "MapData",
+            ServerMessage::MatchState { .. } => "MatchState",
+            ServerMessage::RoundResults { .. } => "RoundResults",
+            ServerMessage::TeamScore { .. } => "TeamScore",
+            ServerMessage::InventoryUpdate { .. } => "InventoryUpdate",
+            ServerMessage::CollectiblePositions(_) => "CollectiblePositions",
+            ServerMessage::EffectStarted { .. } => "EffectStarted",
+            ServerMessage::EffectEnded { .. } => "EffectEnded",
+            ServerMessage::AbilityCooldown { .. } => "AbilityCooldown",
+            ServerMessage::ProjectileSpawned(_) => "ProjectileSpawned",
+            ServerMessage::ProjectileDespawned { .. } => "ProjectileDespawned",
+            ServerMessage::PlayerHit { .. } => "PlayerHit",
+            ServerMessage::SpawnDrones(_) => "SpawnDrones",
+            ServerMessage::DronePositions(_) => "DronePositions",
+            ServerMessage::Chat { .. } => "Chat",
+            ServerMessage::PlayerRenamed { .. } => "PlayerRenamed",
+            ServerMessage::Kicked { .. } => "Kicked",
+        }
+    }
+}
+
+/// Sent by the client in the netcode connect token's user data
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ConnectData {
+    /// Token from a previous session to resume
+    pub resume_token: Option<u64>,
+    /// Requested name and color; the server may change either to keep them unique
+    pub profile: PlayerProfile,
+    /// Watch without a player of our own
+    pub spectate: bool,
+}
+
+/// How a player shows up to everyone else
+#[derive(Component, Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PlayerProfile {
+    pub name: String,
+    /// Index into [`PLAYER_COLORS`]
+    pub color: u8,
+    /// Team asked for, or `None` to be balanced automatically. The server settles on a team in
+    /// team mode and clears it otherwise.
+    pub team: Option<Team>,
+}
+
+impl PlayerProfile {
+    /// Whether the requested name is one to work with: not blank, at most [`MAX_NAME_LEN`]
+    /// characters and free of control characters
+    pub fn has_valid_name(&self) -> bool {
+        let name = self.name.trim();
+        !name.is_empty()
+            && name.chars().count() <= MAX_NAME_LEN
+            && !name.chars().any(char::is_control)
+    }
+
+    /// The team's color in team mode, otherwise the player's own
+    pub fn color(&self) -> Color {
+        match self.team {
+            Some(team) => team.color(),
+            None => PLAYER_COLORS[self.color as usize % PLAYER_COLORS.len()],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Team {
+    Red,
+    Blue,
+}
+
+impl Team {
+    pub const ALL: [Team; 2] = [Team::Red, Team::Blue];
+
+    pub fn color(self) -> Color {
+        match self {
+            Team::Red => Color::srgb(0.9, 0.25, 0.25),
+            Team::Blue => Color::srgb(0.25, 0.45, 1.0),
+        }
+    }
+}
+
+/// Who a chat line was sent to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ChatScope {
+    All,
+    /// Only the sender's teammates
+    Team(Team),
+    /// Only the sender and the named player
+    Whisper {
+        to: String,
+    },
+    /// From the server itself, e.g. command replies
+    Server,
+}
+
+impl ConnectData {
+    /// Fails if the data doesn't fit in a connect token
+    pub fn to_user_data(
+        &self,
+    ) -> Result<[u8; NETCODE_USER_DATA_BYTES], bincode::error::EncodeError> {
+        let mut data = [0; NETCODE_USER_DATA_BYTES];
+        bincode::serde::encode_into_slice(self, &mut data, bincode::config::standard())?;
+        Ok(data)
+    }
+
+    /// Decodes what a client sent, or `None` if it doesn't decode or asks for a name the
+    /// server won't accept
+    pub fn from_user_data(data: &[u8; NETCODE_USER_DATA_BYTES]) -> Option<Self> {
+        let config = bincode::config::standard().with_limit::<{ NETCODE_USER_DATA_BYTES }>();
+        bincode::serde::decode_from_slice::<Self, _>(data, config)
+            .ok()
+            .map(|(connect_data, _)| connect_data)
+            .filter(|connect_data| connect_data.profile.has_valid_name())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PositionData {
     pub client_id: u64,
     pub position: Vec3,
     pub rotation: Quat,
+    /// Lets clients extrapolate between updates
+    pub velocity: Vec2,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProjectileInfo {
+    pub id: u64,
+    /// Client ID of the player who fired it
+    pub owner: u64,
+    pub position: Vec3,
+    pub velocity: Vec2,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DroneData {
+    pub id: u64,
+    pub position: Vec3,
+    pub velocity: Vec2,
+}
+
+pub const DRONE_RADIUS: f32 = 12.0;
+
+/// Phases of the server's match state machine
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchPhase {
+    /// Waiting for players; collecting is allowed but scores are reset before the round
+    Warmup,
+    Countdown,
+    Playing,
+    RoundOver,
+    Intermission,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoundStanding {
+    pub client_id: u64,
+    pub name: String,
+    pub team: Option<Team>,
+    pub score: u32,
 }
 
 /// Basic info for spawning collectibles client-side
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CollectibleInfo {
     pub id: u64,
     pub position: Vec3,
+    pub kind: CollectibleKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CollectiblePosition {
+    pub id: u64,
+    pub position: Vec3,
+}
+
+/// What a collectible gives the player who picks it up
+#[derive(Component, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectibleKind {
+    Points(u32),
+    PowerUp(PowerUp),
+}
+
+impl CollectibleKind {
+    pub fn points(self) -> u32 {
+        match self {
+            CollectibleKind::Points(points) => points,
+            CollectibleKind::PowerUp(_) => 0,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            CollectibleKind::Points(1) => Color::srgb(1.0, 1.0, 0.0),
+            CollectibleKind::Points(_) => Color::srgb(1.0, 0.6, 0.0),
+            CollectibleKind::PowerUp(power_up) => power_up.color(),
+        }
+    }
+
+    /// How much carrying it slows a player down; power-ups take effect at once and weigh nothing
+    pub fn weight(self) -> u32 {
+        self.points()
+    }
+
+    /// Side length of the collectible's square, bigger for more points
+    pub fn size(self) -> f32 {
+        match self {
+            CollectibleKind::Points(points) => 16.0 + 4.0 * points.min(4) as f32,
+            CollectibleKind::PowerUp(_) => 22.0,
+        }
+    }
+}
+
+/// Actions a player can trigger, each with its own cooldown
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Ability {
+    /// A burst of speed in the given direction
+    Dash,
+}
+
+impl Ability {
+    pub fn cooldown_secs(self) -> f32 {
+        match self {
+            Ability::Dash => 2.0,
+        }
+    }
+}
+
+/// Speed a dash adds to the player
+pub const DASH_SPEED: f32 = 700.0;
+
+/// Timed effects granted by power-up collectibles
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerUp {
+    /// Faster acceleration
+    SpeedBoost,
+    /// Can't be pushed around by other players
+    Shield,
+    /// Pulls nearby collectibles in
+    Magnet,
+}
+
+impl PowerUp {
+    pub fn color(self) -> Color {
+        match self {
+            PowerUp::SpeedBoost => Color::srgb(0.2, 1.0, 0.4),
+            PowerUp::Shield => Color::srgb(0.3, 0.8, 1.0),
+            PowerUp::Magnet => Color::srgb(1.0, 0.3, 0.5),
+        }
+    }
 }
 
 /// Enum for identifying outbound server channels
@@ -76,9 +524,14 @@ impl From<ServerChannel> for u8 {
 
 /// Enum for identifying inbound client channels
 #[repr(u8)]
+#[derive(Clone, Copy)]
 pub enum ClientChannel {
     /// For input messages
     Input = 0,
+    /// For abilities and other one-off actions
+    Action = 1,
+    /// For chat, so a flood of messages never holds up gameplay
+    Chat = 2,
 }
 
 impl From<ClientChannel> for u8 {
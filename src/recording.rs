@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::PROTOCOL_ID;
+
+/// Written once at the start of every recording file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordingHeader {
+    pub protocol_id: u64,
+    /// Wall-clock start of the recording, in milliseconds since the unix epoch
+    pub started_at_ms: u64,
+}
+
+/// Where a recorded payload was travelling
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    /// A message the server accepted from a client
+    FromClient { client_id: u64 },
+    /// A message the server sent to a single client
+    ToClient { client_id: u64 },
+    /// A message the server sent to every client
+    Broadcast,
+}
+
+/// A single encoded message, stored exactly as it went over the wire
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RecordedPacket {
+    pub tick: u64,
+    /// Seconds since the recording started
+    pub time: f64,
+    pub route: Route,
+    pub channel: u8,
+    pub payload: Vec<u8>,
+}
+
+/// A recording loaded back from disk
+pub struct Recording {
+    pub header: RecordingHeader,
+    pub packets: Vec<RecordedPacket>,
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let config = bincode::config::standard();
+
+        let (header, mut offset) =
+            bincode::serde::decode_from_slice::<RecordingHeader, _>(&bytes, config)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        if header.protocol_id != PROTOCOL_ID {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("recorded with protocol {:#x}", header.protocol_id),
+            ));
+        }
+
+        let mut packets = Vec::new();
+        while offset < bytes.len() {
+            match bincode::serde::decode_from_slice::<RecordedPacket, _>(&bytes[offset..], config) {
+                Ok((packet, read)) => {
+                    packets.push(packet);
+                    offset += read;
+                }
+                Err(e) => {
+                    // The server may have been killed mid-write; keep what we have
+                    warn!("Recording truncated after {} packets: {e}", packets.len());
+                    break;
+                }
+            }
+        }
+
+        Ok(Self { header, packets })
+    }
+
+    /// Length of the recording in seconds
+    pub fn duration(&self) -> f64 {
+        self.packets.last().map(|p| p.time).unwrap_or(0.0)
+    }
+}
+
+/// Appends every packet passing through the server to a file
+#[derive(Resource)]
+pub struct Recorder {
+    writer: BufWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        let header = RecordingHeader {
+            protocol_id: PROTOCOL_ID,
+            started_at_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64,
+        };
+        bincode::serde::encode_into_std_write(&header, &mut writer, bincode::config::standard())
+            .map_err(io::Error::other)?;
+
+        Ok(Self {
+            writer,
+            started: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, tick: u64, route: Route, channel: u8, payload: &[u8]) {
+        let packet = RecordedPacket {
+            tick,
+            time: self.started.elapsed().as_secs_f64(),
+            route,
+            channel,
+            payload: payload.to_vec(),
+        };
+
+        if let Err(e) = bincode::serde::encode_into_std_write(
+            &packet,
+            &mut self.writer,
+            bincode::config::standard(),
+        ) {
+            error!("Failed to write recorded packet: {e}");
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            error!("Failed to flush recording: {e}");
+        }
+    }
+}
+
+/// Flushes the recorder once per frame so a killed server still leaves a usable file
+pub fn flush_recorder(recorder: Option<ResMut<Recorder>>) {
+    if let Some(mut recorder) = recorder {
+        recorder.flush();
+    }
+}
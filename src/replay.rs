@@ -0,0 +1,433 @@
+use crate::{
+    BoxCollectable, ServerMessage,
+    client::name_label,
+    map::MapDefinition,
+    protocol::{CollectibleInfo, DRONE_RADIUS, MatchPhase, PlayerProfile},
+    recording::{Recording, Route},
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+
+const SEEK_STEP_SECS: f64 = 5.0;
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 8.0;
+
+pub fn run(args: &[String]) {
+    let Some(path) = args.first() else {
+        eprintln!("Usage: cargo run --bin main -- replay <file>");
+        return;
+    };
+
+    let recording = match Recording::load(path) {
+        Ok(recording) => recording,
+        Err(e) => {
+            eprintln!("Failed to load recording {path}: {e}");
+            return;
+        }
+    };
+    println!(
+        "Loaded {} packets recorded at {} (unix ms)",
+        recording.packets.len(),
+        recording.header.started_at_ms
+    );
+
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .insert_resource(Playback::new(recording))
+        .add_systems(Startup, setup_viewer)
+        .add_systems(
+            Update,
+            (
+                playback_controls,
+                advance_playback,
+                sync_players,
+                sync_map,
+                sync_collectibles,
+                sync_drones,
+                update_hud,
+            )
+                .chain(),
+        )
+        .run();
+}
+
+fn setup_viewer(mut commands: Commands) {
+    commands.spawn(Camera2d);
+
+    commands.spawn((
+        ReplayHud,
+        Text::default(),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+    ));
+}
+
+// === Playback ===
+fn playback_controls(keys: Res<ButtonInput<KeyCode>>, mut playback: ResMut<Playback>) {
+    if keys.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+    }
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        playback.speed = (playback.speed * 2.0).min(MAX_SPEED);
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        playback.speed = (playback.speed / 2.0).max(MIN_SPEED);
+    }
+    if keys.just_pressed(KeyCode::ArrowRight) {
+        let target = playback.time + SEEK_STEP_SECS;
+        playback.seek(target);
+    }
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        let target = playback.time - SEEK_STEP_SECS;
+        playback.seek(target);
+    }
+    if keys.just_pressed(KeyCode::Home) {
+        playback.seek(0.0);
+    }
+}
+
+fn advance_playback(time: Res<Time>, mut playback: ResMut<Playback>) {
+    if playback.paused {
+        return;
+    }
+
+    let target = playback.time + (time.delta_secs() * playback.speed) as f64;
+    playback.seek(target);
+
+    if playback.time >= playback.duration {
+        playback.paused = true;
+    }
+}
+
+fn sync_players(
+    mut commands: Commands,
+    playback: Res<Playback>,
+    mut players: Query<(Entity, &ReplayPlayer, &mut Transform, &PlayerProfile)>,
+) {
+    for (entity, player, mut transform, profile) in players.iter_mut() {
+        match playback.world.players.get(&player.client_id) {
+            // Respawn players whose profile changed so the label is rebuilt too
+            Some(state) if state.profile == *profile => {
+                transform.translation = state.position;
+                transform.rotation = state.rotation;
+            }
+            _ => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (client_id, state) in playback.world.players.iter() {
+        if players.iter().any(|(_, player, _, profile)| {
+            player.client_id == *client_id && *profile == state.profile
+        }) {
+            continue;
+        }
+        commands.spawn((
+            ReplayPlayer {
+                client_id: *client_id,
+            },
+            state.profile.clone(),
+            Transform::from_translation(state.position).with_rotation(state.rotation),
+            Sprite {
+                color: state.profile.color(),
+                custom_size: Some(Vec2::splat(30.0)),
+                ..default()
+            },
+            children![name_label(&state.profile.name)],
+        ));
+    }
+}
+
+fn sync_map(
+    mut commands: Commands,
+    playback: Res<Playback>,
+    tiles: Query<(Entity, &ReplayMapTile)>,
+) {
+    // Maps only change when seeking or when an admin switched maps mid-recording
+    let shown = tiles.iter().next().map(|(_, tile)| tile.0.as_str());
+    let wanted = playback.world.map.as_ref();
+    if shown == wanted.map(|map| map.name.as_str()) {
+        return;
+    }
+
+    for (entity, _) in tiles.iter() {
+        commands.entity(entity).despawn();
+    }
+    if let Some(map) = wanted {
+        for sprite in map.sprites() {
+            commands.spawn((ReplayMapTile(map.name.clone()), sprite));
+        }
+    }
+}
+
+fn sync_collectibles(
+    mut commands: Commands,
+    playback: Res<Playback>,
+    mut boxes: Query<(Entity, &ReplayCollectible, &mut Transform)>,
+) {
+    for (entity, collectible, mut transform) in boxes.iter_mut() {
+        match playback.world.collectibles.get(&collectible.0) {
+            Some(info) => transform.translation = info.position,
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (id, info) in playback.world.collectibles.iter() {
+        if boxes.iter().any(|(_, collectible, _)| collectible.0 == *id) {
+            continue;
+        }
+        commands.spawn((
+            BoxCollectable,
+            ReplayCollectible(*id),
+            Transform::from_translation(info.position),
+            Sprite {
+                color: info.kind.color(),
+                custom_size: Some(Vec2::splat(info.kind.size())),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn sync_drones(
+    mut commands: Commands,
+    playback: Res<Playback>,
+    mut drones: Query<(Entity, &ReplayDrone, &mut Transform)>,
+) {
+    for (entity, drone, mut transform) in drones.iter_mut() {
+        match playback.world.drones.get(&drone.0) {
+            Some(position) => transform.translation = *position,
+            None => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (id, position) in playback.world.drones.iter() {
+        if drones.iter().any(|(_, drone, _)| drone.0 == *id) {
+            continue;
+        }
+        commands.spawn((
+            ReplayDrone(*id),
+            Transform::from_translation(*position),
+            Sprite {
+                color: Color::srgb(0.55, 0.55, 0.6),
+                custom_size: Some(Vec2::splat(DRONE_RADIUS * 2.0)),
+                ..default()
+            },
+        ));
+    }
+}
+
+fn update_hud(playback: Res<Playback>, mut hud: Query<&mut Text, With<ReplayHud>>) {
+    let Ok(mut text) = hud.single_mut() else {
+        return;
+    };
+
+    let state = if playback.paused { "paused" } else { "playing" };
+    let match_state = playback
+        .world
+        .match_state
+        .map(|(phase, round)| format!("Round {round}  {phase:?}\n"))
+        .unwrap_or_default();
+    let mut scores: Vec<_> = playback.world.players.values().collect();
+    scores.sort_by_key(|player| std::cmp::Reverse(player.score));
+    let scores = scores
+        .iter()
+        .map(|player| format!("{}  {}", player.profile.name, player.score))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    text.0 = format!(
+        "tick {}  {:.1}s / {:.1}s  x{}  [{state}]\n\
+         Space: play/pause  Left/Right: seek  Up/Down: speed  Home: restart\n\n{match_state}{scores}",
+        playback.tick, playback.time, playback.duration, playback.speed,
+    );
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+struct ReplayHud;
+
+#[derive(Component)]
+struct ReplayPlayer {
+    client_id: u64,
+}
+
+#[derive(Component)]
+struct ReplayCollectible(u64);
+
+/// Part of the drawn map, tagged with the map's name
+#[derive(Component)]
+struct ReplayMapTile(String);
+
+#[derive(Component)]
+struct ReplayDrone(u64);
+
+/// A server message decoded from the recording, with when it was sent
+struct ReplayEvent {
+    tick: u64,
+    time: f64,
+    message: ServerMessage,
+}
+
+#[derive(Default)]
+struct ReplayPlayerState {
+    position: Vec3,
+    rotation: Quat,
+    profile: PlayerProfile,
+    score: u32,
+}
+
+/// World state rebuilt from the server messages played so far
+#[derive(Default)]
+struct ReplayWorld {
+    players: HashMap<u64, ReplayPlayerState>,
+    collectibles: HashMap<u64, CollectibleInfo>,
+    drones: HashMap<u64, Vec3>,
+    map: Option<MapDefinition>,
+    /// Latest match phase and round number
+    match_state: Option<(MatchPhase, u32)>,
+}
+
+impl ReplayWorld {
+    fn apply(&mut self, message: &ServerMessage) {
+        match message {
+            ServerMessage::SpawnCollectibles(collectibles) => {
+                for info in collectibles {
+                    self.collectibles.insert(info.id, info.clone());
+                }
+            }
+            ServerMessage::DespawnCollectible { id } => {
+                self.collectibles.remove(id);
+            }
+            ServerMessage::CollectiblePositions(positions) => {
+                for moved in positions {
+                    if let Some(info) = self.collectibles.get_mut(&moved.id) {
+                        info.position = moved.position;
+                    }
+                }
+            }
+            ServerMessage::PlayerPositions { players, .. } => {
+                for data in players {
+                    let state = self.players.entry(data.client_id).or_default();
+                    state.position = data.position;
+                    state.rotation = data.rotation;
+                }
+            }
+            ServerMessage::SpawnRemotePlayer { client_id, profile } => {
+                self.players.entry(*client_id).or_default().profile = profile.clone();
+            }
+            ServerMessage::DespawnPlayer { client_id } => {
+                self.players.remove(client_id);
+            }
+            ServerMessage::PlayerRenamed { client_id, name } => {
+                self.players.entry(*client_id).or_default().profile.name = name.clone();
+            }
+            ServerMessage::ScoreUpdate { client_id, score } => {
+                self.players.entry(*client_id).or_default().score = *score;
+            }
+            ServerMessage::MapData(map) => {
+                self.map = Some(map.clone());
+            }
+            ServerMessage::MatchState { phase, round, .. } => {
+                self.match_state = Some((*phase, *round));
+            }
+            ServerMessage::SpawnDrones(drones) | ServerMessage::DronePositions(drones) => {
+                for drone in drones {
+                    self.drones.insert(drone.id, drone.position);
+                }
+            }
+            ServerMessage::AssignClientId { .. }
+            | ServerMessage::SessionToken { .. }
+            | ServerMessage::RoundResults { .. }
+            | ServerMessage::TeamScore { .. }
+            | ServerMessage::InventoryUpdate { .. }
+            | ServerMessage::EffectStarted { .. }
+            | ServerMessage::EffectEnded { .. }
+            | ServerMessage::AbilityCooldown { .. }
+            | ServerMessage::ProjectileSpawned(_)
+            | ServerMessage::ProjectileDespawned { .. }
+            | ServerMessage::PlayerHit { .. }
+            | ServerMessage::Chat { .. }
+            | ServerMessage::Kicked { .. } => {}
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Playback {
+    events: Vec<ReplayEvent>,
+    /// Index of the next event to apply
+    cursor: usize,
+    time: f64,
+    tick: u64,
+    duration: f64,
+    speed: f32,
+    paused: bool,
+    world: ReplayWorld,
+}
+
+impl Playback {
+    fn new(recording: Recording) -> Self {
+        let duration = recording.duration();
+
+        // Only what the server sent is needed to rebuild the world. Messages sent to a
+        // single client (e.g. the collectible snapshot on connect) are replayed too; applying
+        // them is idempotent.
+        let events = recording
+            .packets
+            .into_iter()
+            .filter(|packet| !matches!(packet.route, Route::FromClient { .. }))
+            .filter_map(|packet| {
+                let (message, _) = bincode::serde::decode_from_slice::<ServerMessage, _>(
+                    &packet.payload,
+                    bincode::config::standard(),
+                )
+                .ok()?;
+                Some(ReplayEvent {
+                    tick: packet.tick,
+                    time: packet.time,
+                    message,
+                })
+            })
+            .collect();
+
+        Self {
+            events,
+            cursor: 0,
+            time: 0.0,
+            tick: 0,
+            duration,
+            speed: 1.0,
+            paused: false,
+            world: ReplayWorld::default(),
+        }
+    }
+
+    /// Moves playback to `target` seconds, rebuilding the world from the start when rewinding
+    fn seek(&mut self, target: f64) {
+        let target = target.clamp(0.0, self.duration);
+
+        if target < self.time {
+            self.world = ReplayWorld::default();
+            self.cursor = 0;
+            self.tick = 0;
+        }
+
+        while let Some(event) = self.events.get(self.cursor) {
+            if event.time > target {
+                break;
+            }
+            self.world.apply(&event.message);
+            self.tick = event.tick;
+            self.cursor += 1;
+        }
+
+        self.time = target;
+    }
+}
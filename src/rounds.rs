@@ -0,0 +1,375 @@
+use crate::{
+    BoxCollectable, ServerMessage, arg_value,
+    inventory::Inventory,
+    map::MapDefinition,
+    parse_secs,
+    protocol::{MatchPhase, PlayerProfile, RoundStanding, Team},
+    server::{
+        CollectibleEntityMap, CollectibleId, DisconnectedPlayer, GameRng, NetworkIds, Player,
+        Score, ServerNetwork,
+    },
+    spawner::{CollectibleSpawner, respawn_collectibles},
+    teams::TeamScores,
+};
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
+use bevy_renet2::prelude::ServerEvent;
+use std::time::Duration;
+
+/// Gap between players when they are lined up for a new round
+const SPAWN_SPACING: f32 = 50.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<MatchState>()
+        .add_systems(Update, (advance_match, send_match_state_on_connect).chain());
+}
+
+// === Match Flow ===
+#[allow(
+    clippy::too_many_arguments,
+    clippy::type_complexity,
+    reason = "starting a round resets every player and collectible"
+)]
+fn advance_match(
+    mut commands: Commands,
+    time: Res<Time>,
+    rules: Res<MatchRules>,
+    map: Res<MapDefinition>,
+    mut state: ResMut<MatchState>,
+    mut network: ServerNetwork,
+    mut collectible_entities: ResMut<CollectibleEntityMap>,
+    mut spawner: ResMut<CollectibleSpawner>,
+    mut ids: ResMut<NetworkIds>,
+    mut rng: ResMut<GameRng>,
+    mut team_scores: ResMut<TeamScores>,
+    boxes: Query<(Entity, &CollectibleId), With<BoxCollectable>>,
+    mut players: Query<(
+        &Player,
+        &PlayerProfile,
+        &mut Score,
+        &mut Inventory,
+        &mut Transform,
+        &mut Velocity,
+        Has<DisconnectedPlayer>,
+    )>,
+) {
+    state.timer.tick(time.delta());
+
+    // Players held for a reconnect take part in resets and standings, but only connected
+    // ones keep the match going
+    let connected = players.iter().filter(|(.., held)| !held).count();
+
+    let next = match state.phase {
+        MatchPhase::Warmup if connected >= rules.min_players => {
+            if state.waiting_for_players {
+                // The warmup clock starts now, so clients can count it down
+                state.waiting_for_players = false;
+                network.broadcast(&state.message());
+            }
+            state.timer.finished().then_some(MatchPhase::Countdown)
+        }
+        MatchPhase::Warmup => {
+            // Not enough players yet, so keep the warmup clock from running out
+            let was_running = !state.waiting_for_players;
+            state.enter(MatchPhase::Warmup, &rules);
+            if was_running {
+                network.broadcast(&state.message());
+            }
+            None
+        }
+        MatchPhase::Countdown => state.timer.finished().then_some(MatchPhase::Playing),
+        MatchPhase::Playing => {
+            let reached_target = rules.score_to_win.is_some_and(|target| match rules.mode {
+                GameMode::FreeForAll | GameMode::Delivery => {
+                    players.iter().any(|(_, _, score, ..)| score.0 >= target)
+                }
+                GameMode::Teams => Team::ALL
+                    .into_iter()
+                    .any(|team| team_scores.get(team) >= target),
+            });
+            (reached_target || state.timer.finished()).then_some(MatchPhase::RoundOver)
+        }
+        MatchPhase::RoundOver => state.timer.finished().then_some(MatchPhase::Intermission),
+        MatchPhase::Intermission if state.timer.finished() => {
+            if connected == 0 {
+                Some(MatchPhase::Warmup)
+            } else {
+                Some(MatchPhase::Countdown)
+            }
+        }
+        MatchPhase::Intermission => None,
+    };
+
+    let Some(next) = next else {
+        return;
+    };
+
+    match next {
+        MatchPhase::Countdown => {
+            state.round += 1;
+
+            // Fresh world: everyone back to the start with no boxes collected
+            respawn_collectibles(
+                &mut commands,
+                &mut network,
+                &mut collectible_entities,
+                &mut spawner,
+                &mut ids,
+                &mut rng,
+                &map,
+                &boxes,
+            );
+
+            if rules.mode == GameMode::Teams {
+                team_scores.reset();
+                for team in Team::ALL {
+                    network.broadcast(&ServerMessage::TeamScore { team, score: 0 });
+                }
+            }
+
+            // Players line up at the map's spawn, or at their own base in team mode
+            let line_start = |profile: &PlayerProfile| {
+                profile
+                    .team
+                    .and_then(|team| map.team_base(team))
+                    .map_or(map.player_spawn, |base| base.center)
+            };
+            let starts: Vec<Vec2> = players
+                .iter()
+                .map(|(_, profile, ..)| line_start(profile))
+                .collect();
+
+            for (i, (player, _, mut score, mut inventory, mut transform, mut velocity, _)) in
+                players.iter_mut().enumerate()
+            {
+                score.0 = 0;
+                network.broadcast(&ServerMessage::ScoreUpdate {
+                    client_id: player.client_id,
+                    score: 0,
+                });
+                if !inventory.0.is_empty() {
+                    inventory.0.clear();
+                    network.broadcast(&inventory.message(player.client_id));
+                }
+
+                let start = starts[i];
+                let place = starts[..i].iter().filter(|other| **other == start).count();
+                let count = starts.iter().filter(|other| **other == start).count();
+                let offset = (place as f32 - (count as f32 - 1.0) / 2.0) * SPAWN_SPACING;
+                transform.translation = (start + Vec2::new(offset, 0.0)).extend(0.0);
+                *velocity = Velocity::zero();
+            }
+        }
+        MatchPhase::RoundOver => {
+            let mut standings: Vec<RoundStanding> = players
+                .iter()
+                .map(|(player, profile, score, ..)| RoundStanding {
+                    client_id: player.client_id,
+                    name: profile.name.clone(),
+                    team: profile.team,
+                    score: score.0,
+                })
+                .collect();
+            standings.sort_by_key(|standing| std::cmp::Reverse(standing.score));
+
+            // A tie at the top is a draw
+            let (winner, winning_team) = match rules.mode {
+                GameMode::FreeForAll | GameMode::Delivery => {
+                    let winner = match standings.as_slice() {
+                        [first, second, ..] if first.score == second.score => None,
+                        [first, ..] if first.score > 0 => Some(first.client_id),
+                        _ => None,
+                    };
+                    (winner, None)
+                }
+                GameMode::Teams => (None, team_scores.leader()),
+            };
+
+            match winning_team {
+                Some(team) => info!("Round {} over, winner: {team:?}", state.round),
+                None => info!("Round {} over, winner: {winner:?}", state.round),
+            }
+            network.broadcast(&ServerMessage::RoundResults {
+                round: state.round,
+                winner,
+                winning_team,
+                standings,
+            });
+        }
+        MatchPhase::Warmup | MatchPhase::Playing | MatchPhase::Intermission => {}
+    }
+
+    state.enter(next, &rules);
+    info!("Match phase: {:?} (round {})", state.phase, state.round);
+    network.broadcast(&state.message());
+}
+
+fn send_match_state_on_connect(
+    mut events: EventReader<ServerEvent>,
+    state: Res<MatchState>,
+    mut network: ServerNetwork,
+) {
+    for event in events.read() {
+        if let ServerEvent::ClientConnected { client_id } = event {
+            network.send(*client_id, &state.message());
+        }
+    }
+}
+
+// === Components and Resources ===
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    /// Every player for themselves; boxes score as soon as they're picked up
+    FreeForAll,
+    /// Two teams; boxes only score once carried back to the team's base
+    Teams,
+    /// Every player for themselves, but boxes only score once carried to a drop-off
+    Delivery,
+}
+
+/// Round timings and win condition, set from the command line
+#[derive(Resource)]
+pub struct MatchRules {
+    pub mode: GameMode,
+    pub warmup: Duration,
+    pub countdown: Duration,
+    pub round: Duration,
+    pub round_over: Duration,
+    pub intermission: Duration,
+    /// Ends the round early once someone (or some team) collects this many boxes
+    pub score_to_win: Option<u32>,
+    /// Players needed before warmup ends
+    pub min_players: usize,
+}
+
+impl MatchRules {
+    /// Fails on a timing that isn't a non-negative number of seconds, or a count that isn't a
+    /// whole number
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let secs = |flag: &str, default: u64| match arg_value(args, flag) {
+            Some(value) => {
+                parse_secs(value).ok_or_else(|| format!("Usage: {flag} <seconds>, got {value:?}"))
+            }
+            None => Ok(Duration::from_secs(default)),
+        };
+        let count = |flag: &str| {
+            arg_value(args, flag)
+                .map(|value| {
+                    value
+                        .parse::<u32>()
+                        .map_err(|_| format!("Usage: {flag} <whole number>, got {value:?}"))
+                })
+                .transpose()
+        };
+
+        Ok(Self {
+            mode: match arg_value(args, "--mode") {
+                Some("teams") => GameMode::Teams,
+                Some("delivery") => GameMode::Delivery,
+                _ => GameMode::FreeForAll,
+            },
+            warmup: secs("--warmup-secs", 10)?,
+            countdown: Duration::from_secs(3),
+            round: secs("--round-secs", 120)?,
+            round_over: Duration::from_secs(5),
+            intermission: secs("--intermission-secs", 5)?,
+            score_to_win: count("--score-to-win")?,
+            min_players: count("--min-players")?.unwrap_or(1) as usize,
+        })
+    }
+
+    /// Changes one rule, named as on the command line without the dashes, e.g. `round-secs`.
+    /// Timings apply from the next phase. The mode is fixed once the server is running.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let secs =
+            || parse_secs(value).ok_or_else(|| format!("Invalid value {value}, expected seconds"));
+        let number = || {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("{value} isn't a whole number"))
+        };
+
+        match name {
+            "warmup-secs" => self.warmup = secs()?,
+            "round-secs" => self.round = secs()?,
+            "intermission-secs" => self.intermission = secs()?,
+            "score-to-win" if value == "off" => self.score_to_win = None,
+            "score-to-win" => self.score_to_win = Some(number()?),
+            "min-players" => self.min_players = number()? as usize,
+            _ => return Err(format!("Unknown rule {name}")),
+        }
+        Ok(())
+    }
+
+    /// One line per rule, named as for [`MatchRules::set`]
+    pub fn summary(&self) -> String {
+        let score_to_win = self
+            .score_to_win
+            .map_or("off".to_string(), |score| score.to_string());
+        [
+            format!("mode: {:?}", self.mode),
+            format!("warmup-secs: {}", self.warmup.as_secs_f32()),
+            format!("round-secs: {}", self.round.as_secs_f32()),
+            format!("intermission-secs: {}", self.intermission.as_secs_f32()),
+            format!("score-to-win: {score_to_win}"),
+            format!("min-players: {}", self.min_players),
+        ]
+        .join("\n")
+    }
+
+    /// Whether boxes are carried around and delivered rather than scored on pickup
+    pub fn carries(&self) -> bool {
+        matches!(self.mode, GameMode::Teams | GameMode::Delivery)
+    }
+
+    fn duration(&self, phase: MatchPhase) -> Duration {
+        match phase {
+            MatchPhase::Warmup => self.warmup,
+            MatchPhase::Countdown => self.countdown,
+            MatchPhase::Playing => self.round,
+            MatchPhase::RoundOver => self.round_over,
+            MatchPhase::Intermission => self.intermission,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct MatchState {
+    pub phase: MatchPhase,
+    pub round: u32,
+    pub timer: Timer,
+    /// Warmup is held until enough players are connected, and its timer isn't running
+    waiting_for_players: bool,
+}
+
+impl Default for MatchState {
+    fn default() -> Self {
+        Self {
+            phase: MatchPhase::Warmup,
+            round: 0,
+            timer: Timer::default(),
+            waiting_for_players: true,
+        }
+    }
+}
+
+impl MatchState {
+    /// Whether boxes can be picked up right now
+    pub fn allows_collecting(&self) -> bool {
+        matches!(self.phase, MatchPhase::Warmup | MatchPhase::Playing)
+    }
+
+    fn enter(&mut self, phase: MatchPhase, rules: &MatchRules) {
+        self.phase = phase;
+        self.timer = Timer::new(rules.duration(phase), TimerMode::Once);
+        self.waiting_for_players = phase == MatchPhase::Warmup;
+    }
+
+    fn message(&self) -> ServerMessage {
+        ServerMessage::MatchState {
+            phase: self.phase,
+            round: self.round,
+            remaining_secs: (!self.waiting_for_players).then(|| self.timer.remaining_secs()),
+        }
+    }
+}
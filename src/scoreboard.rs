@@ -0,0 +1,121 @@
+use crate::{protocol::Team, screens::ClientState};
+use bevy::{platform::collections::HashMap, prelude::*};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Scoreboard>()
+        .add_systems(OnEnter(ClientState::Connecting), clear_scoreboard)
+        .add_systems(OnEnter(ClientState::InGame), spawn_scoreboard)
+        .add_systems(
+            Update,
+            update_scoreboard.run_if(in_state(ClientState::InGame)),
+        );
+}
+
+// === Scoreboard ===
+fn clear_scoreboard(mut scoreboard: ResMut<Scoreboard>) {
+    scoreboard.entries.clear();
+}
+
+fn spawn_scoreboard(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(ClientState::InGame),
+        ScoreboardPanel,
+        Text::default(),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Percent(35.0),
+            width: Val::Percent(30.0),
+            padding: UiRect::all(Val::Px(12.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        Visibility::Hidden,
+    ));
+}
+
+/// Shows the scoreboard while Tab is held
+fn update_scoreboard(
+    keys: Res<ButtonInput<KeyCode>>,
+    scoreboard: Res<Scoreboard>,
+    mut panel: Query<(&mut Text, &mut Visibility), With<ScoreboardPanel>>,
+) {
+    let Ok((mut text, mut visibility)) = panel.single_mut() else {
+        return;
+    };
+
+    if !keys.pressed(KeyCode::Tab) {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    *visibility = Visibility::Inherited;
+    text.0 = format!("Scoreboard\n\n{}", scoreboard.table());
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+struct ScoreboardPanel;
+
+pub struct ScoreEntry {
+    pub name: String,
+    pub team: Option<Team>,
+    pub score: u32,
+}
+
+/// Everyone's score as last reported by the server. Kept after a disconnect so the final
+/// leaderboard can be shown.
+#[derive(Resource, Default)]
+pub struct Scoreboard {
+    pub entries: HashMap<u64, ScoreEntry>,
+}
+
+impl Scoreboard {
+    pub fn set_name(&mut self, client_id: u64, name: &str) {
+        self.entry(client_id).name = name.to_string();
+    }
+
+    pub fn set_team(&mut self, client_id: u64, team: Option<Team>) {
+        self.entry(client_id).team = team;
+    }
+
+    pub fn set_score(&mut self, client_id: u64, score: u32) {
+        self.entry(client_id).score = score;
+    }
+
+    pub fn remove(&mut self, client_id: u64) {
+        self.entries.remove(&client_id);
+    }
+
+    /// Entries sorted from highest to lowest score
+    pub fn ranked(&self) -> Vec<&ScoreEntry> {
+        let mut ranked: Vec<_> = self.entries.values().collect();
+        ranked.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+        ranked
+    }
+
+    /// One line per player, e.g. `1. Alice  12`, or `1. [Red] Alice  12` in team mode
+    pub fn table(&self) -> String {
+        self.ranked()
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| match entry.team {
+                Some(team) => format!("{}. [{team:?}] {}  {}", i + 1, entry.name, entry.score),
+                None => format!("{}. {}  {}", i + 1, entry.name, entry.score),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn entry(&mut self, client_id: u64) -> &mut ScoreEntry {
+        self.entries.entry(client_id).or_insert_with(|| ScoreEntry {
+            name: format!("Player {client_id}"),
+            team: None,
+            score: 0,
+        })
+    }
+}
@@ -0,0 +1,569 @@
+use crate::{
+    client::{ClientSettings, ConnectTimeout},
+    controls::{Action, Binding, Controls, RESERVED_KEYS},
+    protocol::{MAX_NAME_LEN, PLAYER_COLORS, Team},
+    scoreboard::Scoreboard,
+};
+use bevy::{
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+};
+
+const MAX_ADDRESS_LEN: usize = 64;
+const BUTTON_COLOR: Color = Color::srgb(0.15, 0.15, 0.2);
+const BUTTON_HOVER_COLOR: Color = Color::srgb(0.25, 0.25, 0.35);
+const FIELD_COLOR: Color = Color::srgb(0.08, 0.08, 0.1);
+const FIELD_FOCUSED_COLOR: Color = Color::srgb(0.12, 0.12, 0.2);
+
+/// Top-level flow of the client app
+#[derive(States, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ClientState {
+    #[default]
+    MainMenu,
+    Connecting,
+    InGame,
+    Disconnected,
+    Controls,
+}
+
+/// Shown on the disconnect screen
+#[derive(Resource, Default)]
+pub struct LastDisconnect(pub String);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_state::<ClientState>()
+        .enable_state_scoped_entities::<ClientState>()
+        .init_resource::<LastDisconnect>()
+        .init_resource::<FocusedField>()
+        .init_resource::<Rebinding>()
+        .add_systems(OnEnter(ClientState::MainMenu), spawn_main_menu)
+        .add_systems(OnEnter(ClientState::Connecting), spawn_connecting_screen)
+        .add_systems(
+            OnEnter(ClientState::Disconnected),
+            spawn_disconnected_screen,
+        )
+        .add_systems(OnEnter(ClientState::Controls), spawn_controls_screen)
+        .add_systems(
+            Update,
+            (
+                focus_text_fields,
+                type_into_focused_field,
+                update_text_fields,
+                update_color_swatch,
+                update_team_choice,
+            )
+                .chain()
+                .run_if(in_state(ClientState::MainMenu)),
+        )
+        .add_systems(
+            Update,
+            update_connecting_screen.run_if(in_state(ClientState::Connecting)),
+        )
+        .add_systems(
+            Update,
+            // Before the buttons, so the click that starts a rebind isn't taken as the binding
+            (capture_binding, update_binding_buttons)
+                .before(handle_menu_buttons)
+                .run_if(in_state(ClientState::Controls)),
+        )
+        .add_systems(Update, (handle_menu_buttons, highlight_buttons));
+}
+
+// === Screens ===
+fn spawn_main_menu(mut commands: Commands, mut focused: ResMut<FocusedField>) {
+    focused.0 = Some(FieldKind::Name);
+
+    commands
+        .spawn(screen_root(ClientState::MainMenu))
+        .with_children(|parent| {
+            parent.spawn(heading("Bevy Multiplayer"));
+            parent.spawn(label("Server address"));
+            parent.spawn(text_field(FieldKind::ServerAddress));
+            parent.spawn(label("Name"));
+            parent.spawn(text_field(FieldKind::Name));
+            parent.spawn(label("Color (click to change)"));
+            parent.spawn((
+                Button,
+                MenuButton::CycleColor,
+                ColorSwatch,
+                Node {
+                    width: Val::Px(40.0),
+                    height: Val::Px(40.0),
+                    ..default()
+                },
+                BackgroundColor(PLAYER_COLORS[0]),
+            ));
+            parent.spawn(label("Team, in team mode (click to change)"));
+            parent.spawn(button("Auto", MenuButton::CycleTeam));
+            parent.spawn(button("Connect", MenuButton::Connect));
+            parent.spawn(button("Spectate", MenuButton::Spectate));
+            parent.spawn(button("Controls", MenuButton::Controls));
+        });
+}
+
+fn spawn_controls_screen(mut commands: Commands, mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+
+    commands
+        .spawn(screen_root(ClientState::Controls))
+        .with_children(|parent| {
+            parent.spawn(heading("Controls"));
+            parent.spawn(label(
+                "Click an action, then press a key, mouse button or gamepad button",
+            ));
+            let reserved: Vec<String> = RESERVED_KEYS
+                .iter()
+                .map(|key| Binding::Key(*key).label())
+                .collect();
+            parent.spawn(label(&format!(
+                "{} are kept for chat, the scoreboard, minimap, zoom and spectating",
+                reserved.join(", ")
+            )));
+            for action in Action::ALL {
+                parent.spawn(binding_button(action));
+            }
+            parent.spawn(button("Reset to defaults", MenuButton::ResetControls));
+            parent.spawn(button("Back", MenuButton::MainMenu));
+        });
+}
+
+fn spawn_connecting_screen(mut commands: Commands, settings: Res<ClientSettings>) {
+    commands
+        .spawn(screen_root(ClientState::Connecting))
+        .with_children(|parent| {
+            parent.spawn(heading(&format!("Connecting to {}", settings.server_addr)));
+            parent.spawn((ConnectingProgress, label("")));
+            parent.spawn(button("Cancel", MenuButton::MainMenu));
+        });
+}
+
+fn spawn_disconnected_screen(
+    mut commands: Commands,
+    last_disconnect: Res<LastDisconnect>,
+    scoreboard: Res<Scoreboard>,
+) {
+    commands
+        .spawn(screen_root(ClientState::Disconnected))
+        .with_children(|parent| {
+            parent.spawn(heading("Disconnected"));
+            parent.spawn(label(&last_disconnect.0));
+            if !scoreboard.entries.is_empty() {
+                parent.spawn(label(&format!("Final scores\n\n{}", scoreboard.table())));
+            }
+            parent.spawn(button("Retry", MenuButton::Retry));
+            parent.spawn(button("Main menu", MenuButton::MainMenu));
+        });
+}
+
+fn update_connecting_screen(
+    timeout: Option<Res<ConnectTimeout>>,
+    mut progress: Query<&mut Text, With<ConnectingProgress>>,
+) {
+    let (Some(timeout), Ok(mut text)) = (timeout, progress.single_mut()) else {
+        return;
+    };
+
+    text.0 = format!(
+        "{:.0}s / {:.0}s",
+        timeout.0.elapsed_secs(),
+        timeout.0.duration().as_secs_f32()
+    );
+}
+
+// === Buttons ===
+fn handle_menu_buttons(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut settings: ResMut<ClientSettings>,
+    mut controls: ResMut<Controls>,
+    mut rebinding: ResMut<Rebinding>,
+    mut next_state: ResMut<NextState<ClientState>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            MenuButton::Connect | MenuButton::Spectate => {
+                settings.spectate = matches!(button, MenuButton::Spectate);
+                if settings.is_complete() {
+                    next_state.set(ClientState::Connecting);
+                }
+            }
+            MenuButton::Retry => {
+                if settings.is_complete() {
+                    next_state.set(ClientState::Connecting);
+                }
+            }
+            MenuButton::CycleColor => {
+                settings.color = (settings.color + 1) % PLAYER_COLORS.len() as u8;
+            }
+            MenuButton::CycleTeam => {
+                settings.team = match settings.team {
+                    None => Some(Team::Red),
+                    Some(Team::Red) => Some(Team::Blue),
+                    Some(Team::Blue) => None,
+                };
+            }
+            MenuButton::Controls => next_state.set(ClientState::Controls),
+            MenuButton::Rebind(action) => rebinding.0 = Some(*action),
+            MenuButton::ResetControls => {
+                rebinding.0 = None;
+                controls.reset();
+            }
+            MenuButton::MainMenu => next_state.set(ClientState::MainMenu),
+        }
+    }
+}
+
+#[allow(
+    clippy::type_complexity,
+    reason = "text fields and the color swatch are colored by their own systems"
+)]
+fn highlight_buttons(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (
+            Changed<Interaction>,
+            With<Button>,
+            Without<TextField>,
+            Without<ColorSwatch>,
+        ),
+    >,
+) {
+    for (interaction, mut background) in buttons.iter_mut() {
+        background.0 = match interaction {
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        };
+    }
+}
+
+// === Rebinding ===
+/// While an action is waiting for a binding, the next key, mouse or gamepad button pressed
+/// becomes its binding, other than the reserved keys. Escape cancels, or leaves the screen if
+/// nothing is waiting.
+fn capture_binding(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut rebinding: ResMut<Rebinding>,
+    mut controls: ResMut<Controls>,
+    mut next_state: ResMut<NextState<ClientState>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        if rebinding.0.take().is_none() {
+            next_state.set(ClientState::MainMenu);
+        }
+        return;
+    }
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+
+    let binding = keys
+        .get_just_pressed()
+        .find(|key| !RESERVED_KEYS.contains(key))
+        .map(|key| Binding::Key(*key))
+        .or_else(|| mouse.get_just_pressed().next().map(|b| Binding::Mouse(*b)))
+        .or_else(|| {
+            gamepads
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next())
+                .map(|button| Binding::Gamepad(*button))
+        });
+    if let Some(binding) = binding {
+        controls.rebind(action, binding);
+        rebinding.0 = None;
+    }
+}
+
+fn update_binding_buttons(
+    controls: Res<Controls>,
+    rebinding: Res<Rebinding>,
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (button, children) in buttons.iter() {
+        let MenuButton::Rebind(action) = *button else {
+            continue;
+        };
+        let bound = if rebinding.0 == Some(action) {
+            "press a button...".to_string()
+        } else {
+            let labels: Vec<String> = controls
+                .bindings(action)
+                .iter()
+                .map(Binding::label)
+                .collect();
+            if labels.is_empty() {
+                "unbound".to_string()
+            } else {
+                labels.join(", ")
+            }
+        };
+        let line = format!("{}: {bound}", action.label());
+
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                text.0.clone_from(&line);
+            }
+        }
+    }
+}
+
+// === Text Fields ===
+fn focus_text_fields(
+    fields: Query<(&Interaction, &TextField), Changed<Interaction>>,
+    mut focused: ResMut<FocusedField>,
+) {
+    for (interaction, field) in fields.iter() {
+        if *interaction == Interaction::Pressed {
+            focused.0 = Some(field.0);
+        }
+    }
+}
+
+fn type_into_focused_field(
+    mut events: EventReader<KeyboardInput>,
+    mut focused: ResMut<FocusedField>,
+    mut settings: ResMut<ClientSettings>,
+    mut next_state: ResMut<NextState<ClientState>>,
+) {
+    for event in events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Enter => {
+                settings.spectate = false;
+                if settings.is_complete() {
+                    next_state.set(ClientState::Connecting);
+                }
+                continue;
+            }
+            Key::Tab => {
+                focused.0 = Some(match focused.0 {
+                    Some(FieldKind::ServerAddress) => FieldKind::Name,
+                    _ => FieldKind::ServerAddress,
+                });
+                continue;
+            }
+            _ => {}
+        }
+
+        let Some(kind) = focused.0 else {
+            continue;
+        };
+        let (value, max_len) = match kind {
+            FieldKind::ServerAddress => (&mut settings.server_addr, MAX_ADDRESS_LEN),
+            FieldKind::Name => (&mut settings.name, MAX_NAME_LEN),
+        };
+
+        match &event.logical_key {
+            Key::Backspace => {
+                value.pop();
+            }
+            Key::Space if value.chars().count() < max_len => value.push(' '),
+            Key::Character(text) => {
+                for c in text.chars().filter(|c| !c.is_control()) {
+                    if value.chars().count() < max_len {
+                        value.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_text_fields(
+    settings: Res<ClientSettings>,
+    focused: Res<FocusedField>,
+    mut fields: Query<(&TextField, &mut Text, &mut BackgroundColor)>,
+) {
+    for (field, mut text, mut background) in fields.iter_mut() {
+        let value = match field.0 {
+            FieldKind::ServerAddress => &settings.server_addr,
+            FieldKind::Name => &settings.name,
+        };
+        let is_focused = focused.0 == Some(field.0);
+
+        text.0 = if is_focused {
+            format!("{value}|")
+        } else {
+            value.clone()
+        };
+        background.0 = if is_focused {
+            FIELD_FOCUSED_COLOR
+        } else {
+            FIELD_COLOR
+        };
+    }
+}
+
+fn update_color_swatch(
+    settings: Res<ClientSettings>,
+    mut swatches: Query<&mut BackgroundColor, With<ColorSwatch>>,
+) {
+    for mut background in swatches.iter_mut() {
+        background.0 = PLAYER_COLORS[settings.color as usize];
+    }
+}
+
+fn update_team_choice(
+    settings: Res<ClientSettings>,
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let choice = settings
+        .team
+        .map_or("Auto".to_string(), |team| format!("{team:?}"));
+
+    for (button, children) in buttons.iter() {
+        if !matches!(button, MenuButton::CycleTeam) {
+            continue;
+        }
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(child) {
+                text.0.clone_from(&choice);
+            }
+        }
+    }
+}
+
+// === Widgets ===
+fn screen_root(state: ClientState) -> impl Bundle {
+    (
+        StateScoped(state),
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(12.0),
+            ..default()
+        },
+    )
+}
+
+fn heading(text: &str) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 36.0,
+            ..default()
+        },
+    )
+}
+
+fn label(text: &str) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+    )
+}
+
+fn text_field(kind: FieldKind) -> impl Bundle {
+    (
+        Button,
+        TextField(kind),
+        Text::default(),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        Node {
+            width: Val::Px(300.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(FIELD_COLOR),
+    )
+}
+
+fn button(text: &str, action: MenuButton) -> impl Bundle {
+    (
+        Button,
+        action,
+        Node {
+            width: Val::Px(200.0),
+            height: Val::Px(40.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+        children![(
+            Text::new(text),
+            TextFont {
+                font_size: 20.0,
+                ..default()
+            },
+        )],
+    )
+}
+
+/// A wide button showing an action and what it's bound to
+fn binding_button(action: Action) -> impl Bundle {
+    (
+        Button,
+        MenuButton::Rebind(action),
+        Node {
+            width: Val::Px(520.0),
+            height: Val::Px(36.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+        children![(
+            Text::new(action.label()),
+            TextFont {
+                font_size: 18.0,
+                ..default()
+            },
+        )],
+    )
+}
+
+// === Components and Resources ===
+#[derive(Component, Clone, Copy)]
+enum MenuButton {
+    Connect,
+    Spectate,
+    Controls,
+    Rebind(Action),
+    ResetControls,
+    CycleColor,
+    CycleTeam,
+    Retry,
+    MainMenu,
+}
+
+/// The action waiting for a new binding on the controls screen
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    ServerAddress,
+    Name,
+}
+
+#[derive(Component)]
+struct TextField(FieldKind);
+
+#[derive(Resource, Default)]
+struct FocusedField(Option<FieldKind>);
+
+#[derive(Component)]
+struct ConnectingProgress;
+
+#[derive(Component)]
+struct ColorSwatch;
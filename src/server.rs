@@ -1,11 +1,34 @@
 #[cfg(feature = "dev")]
 use crate::dev_tools;
 use crate::{
-    BoxCollectable, ClientMessage, CollectibleInfo, MAX_ACCELERATION, PROTOCOL_ID, ServerChannel,
-    ServerMessage, connection_config,
-    protocol::{PositionData, SERVER_HOST},
+    BoxCollectable, ClientChannel, ClientMessage, CollectibleInfo, MAX_ACCELERATION,
+    PLAYER_LINEAR_DAMPING, PROTOCOL_ID, ServerChannel, ServerMessage,
+    abilities::{self, AbilityCooldowns, use_ability},
+    admin::{self, AdminConfig, BanList, Moderation},
+    anticheat::{self, CheatWatch},
+    arg_value,
+    bumps::{self, CollisionRules, PreStepVelocity},
+    chat::{self, ChatReceived},
+    connection_config,
+    drones::{self, DroneRules},
+    flood::{self, FloodGuard},
+    inspect::Inspector,
+    inventory::{self, Inventory},
+    map::{DEFAULT_MAP, MapDefinition},
+    parse_secs,
+    powerups::{self, ActiveEffects, apply_power_up},
+    projectiles::{self, PositionHistory, Reloading, Snapshot, Stunned, fire},
+    protocol::{
+        COLLECT_RADIUS, CollectibleKind, ConnectData, INPUT_INTERVAL_SECS, MAX_NAME_LEN,
+        PLAYER_COLORS, PlayerProfile, PositionData, SERVER_HOST, Team,
+    },
+    recording::{Recorder, Route, flush_recorder},
+    rounds::{self, GameMode, MatchRules, MatchState},
+    spawner::{self, CollectibleSpawner, spawn_initial_collectibles},
+    spectators,
+    teams::{self, assign_team},
 };
-use bevy::{color::palettes::css::YELLOW, platform::collections::HashMap, prelude::*};
+use bevy::{ecs::system::SystemParam, platform::collections::HashMap, prelude::*};
 use bevy_rapier2d::{
     plugin::{NoUserData, PhysicsSet, RapierPhysicsPlugin},
     prelude::*,
@@ -16,15 +39,55 @@ use bevy_renet2::{
     },
     prelude::{RenetServer, RenetServerPlugin, ServerEvent},
 };
-use rand::Rng;
+use rand::{Rng, SeedableRng, rngs::StdRng};
 use renet2_netcode::NativeSocket;
 use std::{
     net::UdpSocket,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+const DEFAULT_RECONNECT_GRACE: Duration = Duration::from_secs(30);
+
+const DEFAULT_TICK_RATE: u32 = 60;
+
+/// Most seconds of movement one input may apply, the slice of time clients send
+const MAX_INPUT_DELTA_SECS: f32 = INPUT_INTERVAL_SECS;
+
+/// Extra reach allowed on collection attempts to cover the client's view being slightly stale
+const COLLECT_TOLERANCE: f32 = 30.0;
+
 // === Entry Point ===
-pub fn run() {
+pub fn run(args: &[String]) {
+    let map_path = arg_value(args, "--map").unwrap_or(DEFAULT_MAP);
+    let mut map = match MapDefinition::load(map_path) {
+        Ok(map) => map,
+        Err(e) => {
+            eprintln!("Failed to load map {map_path}: {e}");
+            return;
+        }
+    };
+    info!("Loaded map {}", map.name);
+
+    let match_rules = match MatchRules::from_args(args) {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+    if let Err(e) = prepare_map(&mut map, match_rules.mode) {
+        eprintln!("{e}");
+        return;
+    }
+
+    let config = match ServerConfig::from_args(args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            return;
+        }
+    };
+
     let (server, transport) = new_server();
 
     App::new()
@@ -42,27 +105,85 @@ pub fn run() {
         )
         .add_plugins(NetcodeServerPlugin)
         .add_plugins(RenetServerPlugin)
+        .add_plugins(rounds::plugin)
+        .add_plugins(spawner::plugin)
+        .add_plugins(powerups::plugin)
+        .add_plugins(bumps::plugin)
+        .add_plugins(abilities::plugin)
+        .add_plugins(projectiles::plugin)
+        .add_plugins(drones::plugin)
+        .add_plugins(teams::plugin)
+        .add_plugins(inventory::plugin)
+        .add_plugins(chat::plugin)
+        .add_plugins(admin::plugin)
+        .add_plugins(flood::plugin)
+        .add_plugins(anticheat::plugin)
+        .add_plugins(spectators::plugin)
         .insert_resource(server)
         .insert_resource(transport)
+        .insert_resource(map)
+        .insert_resource(config)
+        .insert_resource(TickRate::from_args(args))
+        .insert_resource(AdminConfig::from_args(args))
+        .insert_resource(BanList::from_args(args))
+        .insert_resource(match_rules)
+        .insert_resource(CollisionRules::from_args(args))
+        .insert_resource(DroneRules::from_args(args))
+        .insert_resource(CollectibleSpawner::default())
+        .insert_resource(GameRng::from_args(args))
+        .insert_resource(NetworkIds::default())
+        .insert_resource(Inspector::from_args(args))
         .insert_resource(PlayerEntityMap::default())
+        .insert_resource(SessionMap::default())
         .insert_resource(CollectibleEntityMap::default())
         .insert_resource(LastPlayerPosition::default())
-        .add_systems(Startup, setup_world)
+        .insert_resource(ServerTick::default())
+        .add_systems(Startup, (setup_world, start_recording))
+        .add_systems(First, advance_tick)
         .add_systems(
             Update,
             (
                 handle_client_connects,
                 receive_from_clients,
                 print_server_events,
+                expire_disconnected_players,
             ),
         )
         .add_systems(
             PostUpdate,
             broadcast_player_positions.in_set(PhysicsSet::Writeback),
         )
+        .add_systems(Last, (flush_recorder, limit_tick_rate))
         .run();
 }
 
+/// Checks the map has what the game mode needs, and strips out zones the mode doesn't use
+/// so clients don't draw them
+pub fn prepare_map(map: &mut MapDefinition, mode: GameMode) -> Result<(), String> {
+    match mode {
+        GameMode::Teams => {
+            if let Some(team) = Team::ALL.into_iter().find(|t| map.team_base(*t).is_none()) {
+                return Err(format!("Map {} has no base for team {team:?}", map.name));
+            }
+        }
+        GameMode::Delivery if map.drop_offs.is_empty() => {
+            return Err(format!(
+                "Map {} has no drop-offs for delivery mode",
+                map.name
+            ));
+        }
+        GameMode::Delivery | GameMode::FreeForAll => {}
+    }
+
+    if mode != GameMode::Teams {
+        map.team_bases.clear();
+    }
+    if mode != GameMode::Delivery {
+        map.drop_offs.clear();
+    }
+    Ok(())
+}
+
 // === Server Initialization ===
 fn new_server() -> (RenetServer, NetcodeServerTransport) {
     let socket = UdpSocket::bind(SERVER_HOST).unwrap();
@@ -82,11 +203,124 @@ fn new_server() -> (RenetServer, NetcodeServerTransport) {
     (server, transport)
 }
 
+// === Networking ===
+/// Wraps the renet server so every message is encoded, recorded and inspected in one place
+#[derive(SystemParam)]
+pub struct ServerNetwork<'w> {
+    server: ResMut<'w, RenetServer>,
+    recorder: Option<ResMut<'w, Recorder>>,
+    inspector: Res<'w, Inspector>,
+    tick: Res<'w, ServerTick>,
+}
+
+impl ServerNetwork<'_> {
+    pub fn clients_id(&self) -> Vec<u64> {
+        self.server.clients_id()
+    }
+
+    /// Round trip time to a client, in seconds
+    pub fn rtt(&self, client_id: u64) -> Option<f64> {
+        self.server
+            .network_info(client_id)
+            .ok()
+            .map(|info| info.rtt)
+    }
+
+    pub fn disconnect(&mut self, client_id: u64) {
+        self.server.disconnect(client_id);
+    }
+
+    pub fn send(&mut self, client_id: u64, message: &ServerMessage) {
+        let bytes = bincode::serde::encode_to_vec(message, bincode::config::standard()).unwrap();
+        self.record(
+            Route::ToClient { client_id },
+            ServerChannel::World.into(),
+            &bytes,
+        );
+        self.server
+            .send_message(client_id, ServerChannel::World, bytes);
+    }
+
+    pub fn broadcast(&mut self, message: &ServerMessage) {
+        let bytes = bincode::serde::encode_to_vec(message, bincode::config::standard()).unwrap();
+        self.record(Route::Broadcast, ServerChannel::World.into(), &bytes);
+        self.server.broadcast_message(ServerChannel::World, bytes);
+    }
+
+    /// Returns the next message from this client that decodes, dropping any that don't.
+    /// Dropped payloads are still recorded so they can be hex dumped by the inspector.
+    /// Input is drained before actions, and actions before chat.
+    pub fn receive(&mut self, client_id: u64) -> Option<ClientMessage> {
+        for channel in [
+            ClientChannel::Input,
+            ClientChannel::Action,
+            ClientChannel::Chat,
+        ] {
+            while let Some(bytes) = self.server.receive_message(client_id, channel) {
+                self.record(Route::FromClient { client_id }, channel.into(), &bytes);
+
+                // Oversized messages are dropped like any other that doesn't decode
+                let Ok(msg) = ClientMessage::decode(&bytes) else {
+                    continue;
+                };
+
+                return Some(msg);
+            }
+        }
+        None
+    }
+
+    fn record(&mut self, route: Route, channel: u8, bytes: &[u8]) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(self.tick.0, route, channel, bytes);
+        }
+        self.inspector
+            .inspect(Some(self.tick.0), route, channel, bytes);
+    }
+}
+
+fn start_recording(mut commands: Commands, config: Res<ServerConfig>) {
+    let Some(path) = &config.record else {
+        return;
+    };
+
+    match Recorder::create(path) {
+        Ok(recorder) => {
+            info!("Recording match to {path}");
+            commands.insert_resource(recorder);
+        }
+        Err(e) => error!("Failed to create recording {path}: {e}"),
+    }
+}
+
+fn advance_tick(mut tick: ResMut<ServerTick>) {
+    tick.0 += 1;
+}
+
+/// Sleeps off whatever is left of the frame so the server runs at the configured tick rate
+fn limit_tick_rate(rate: Res<TickRate>, mut next_tick: Local<Option<Instant>>) {
+    let period = Duration::from_secs_f64(1.0 / rate.0 as f64);
+    let now = Instant::now();
+
+    match *next_tick {
+        Some(next) if next > now => {
+            std::thread::sleep(next - now);
+            *next_tick = Some(next + period);
+        }
+        // Running behind (or just started), so start counting again from now
+        _ => *next_tick = Some(now + period),
+    }
+}
+
 // === World Setup ===
 fn setup_world(
     mut commands: Commands,
     mut config: Query<&mut RapierConfiguration>,
     mut collectible_entities: ResMut<CollectibleEntityMap>,
+    mut spawner: ResMut<CollectibleSpawner>,
+    mut ids: ResMut<NetworkIds>,
+    mut rng: ResMut<GameRng>,
+    map: Res<MapDefinition>,
 ) {
     commands.spawn(Camera2d::default());
 
@@ -94,106 +328,275 @@ fn setup_world(
         config.gravity.y = 0.0;
     }
 
-    for i in 1..4 {
-        let id = random_u64();
-        let position = Vec3::new(i as f32 * 100.0, 0.0, 0.0);
+    spawn_solids(&mut commands, &map);
 
-        let entity = commands
-            .spawn((
-                BoxCollectable,
-                CollectibleId(id),
-                Transform::from_translation(position),
-                Sprite {
-                    color: YELLOW.into(),
-                    custom_size: Some(Vec2::splat(20.0)),
-                    ..default()
-                },
-            ))
-            .id();
+    info!("Game seed: {}", rng.seed);
+    spawn_initial_collectibles(
+        &mut commands,
+        &mut collectible_entities,
+        &mut spawner,
+        &mut ids,
+        &mut rng,
+        &map,
+    );
+}
 
-        collectible_entities.0.insert(id, entity);
+/// Colliders for the map's walls and obstacles
+pub fn spawn_solids(commands: &mut Commands, map: &MapDefinition) {
+    for solid in map.solids() {
+        commands.spawn((
+            MapSolid,
+            RigidBody::Fixed,
+            Collider::cuboid(solid.size.x / 2.0, solid.size.y / 2.0),
+            Transform::from_translation(solid.center.extend(0.0)),
+        ));
     }
 }
 
+/// Spawns a box and registers it, returning what clients need to show it
+pub fn spawn_collectible(
+    commands: &mut Commands,
+    collectible_entities: &mut CollectibleEntityMap,
+    ids: &mut NetworkIds,
+    position: Vec3,
+    kind: CollectibleKind,
+) -> CollectibleInfo {
+    let id = ids.allocate();
+
+    let entity = commands
+        .spawn((
+            BoxCollectable,
+            CollectibleId(id),
+            kind,
+            Transform::from_translation(position),
+            Sprite {
+                color: kind.color(),
+                custom_size: Some(Vec2::splat(kind.size())),
+                ..default()
+            },
+        ))
+        .id();
+
+    collectible_entities.0.insert(id, entity);
+
+    CollectibleInfo { id, position, kind }
+}
+
 // === Handle New Connections ===
-fn handle_client_connects(
+#[allow(
+    clippy::too_many_arguments,
+    reason = "brings a new client up to date on the whole world"
+)]
+pub fn handle_client_connects(
     mut events: EventReader<ServerEvent>,
-    boxes: Query<(&CollectibleId, &Transform), With<BoxCollectable>>,
+    boxes: Query<(&CollectibleId, &CollectibleKind, &Transform), With<BoxCollectable>>,
+    mut disconnected: Query<&mut Player, With<DisconnectedPlayer>>,
+    profiles: Query<&PlayerProfile>,
+    statuses: Query<(&Score, &ActiveEffects)>,
 
-    mut server: ResMut<RenetServer>,
+    mut network: ServerNetwork,
+    transport: Res<NetcodeServerTransport>,
+    config: Res<ServerConfig>,
+    collision_rules: Res<CollisionRules>,
+    match_rules: Res<MatchRules>,
+    map: Res<MapDefinition>,
     mut player_map: ResMut<PlayerEntityMap>,
+    mut sessions: ResMut<SessionMap>,
+    mut watch: ResMut<CheatWatch>,
+    mut moderation: Moderation,
     mut commands: Commands,
 ) {
     for event in events.read() {
         match event {
             ServerEvent::ClientConnected { client_id } => {
-                let entity = commands
-                    .spawn((
-                        Player {
-                            client_id: *client_id,
-                        },
-                        RigidBody::Dynamic,
-                        Collider::cuboid(15.0, 15.0),
-                        Velocity::linear(Vec2::ZERO),
-                        Damping {
-                            linear_damping: 5.0,
-                            angular_damping: 2.0,
-                        },
-                        Transform::from_xyz(0.0, 0.0, 0.0),
-                        GlobalTransform::default(),
-                    ))
-                    .id();
+                if moderation.refuse(&mut network, &transport, *client_id) {
+                    continue;
+                }
 
-                player_map.0.insert(*client_id, entity);
+                let connect_data = transport
+                    .user_data(*client_id)
+                    .and_then(|data| ConnectData::from_user_data(&data))
+                    .unwrap_or_default();
+                // Spectators get no player; they're brought up to date separately
+                if connect_data.spectate {
+                    continue;
+                }
 
-                let snapshot: Vec<CollectibleInfo> = boxes
-                    .iter()
-                    .map(|(id, t)| CollectibleInfo {
-                        id: id.0,
-                        position: t.translation,
-                    })
-                    .collect();
-
-                let msg = ServerMessage::SpawnCollectibles(snapshot);
-                let bytes =
-                    bincode::serde::encode_to_vec(&msg, bincode::config::standard()).unwrap();
-                server.send_message(*client_id, ServerChannel::World, bytes);
-
-                let msg = ServerMessage::AssignClientId {
-                    client_id: *client_id,
+                let resumed = connect_data.resume_token.and_then(|token| {
+                    sessions
+                        .0
+                        .get(&token)
+                        .filter(|entity| disconnected.contains(**entity))
+                        .map(|entity| (token, *entity))
+                });
+
+                let (token, entity, profile) = match resumed {
+                    Some((token, entity)) => {
+                        // The old client ID is gone for good, so move the player over to the
+                        // new one and have everyone respawn it under that ID
+                        if let Ok(mut player) = disconnected.get_mut(entity) {
+                            let old_client_id = player.client_id;
+                            player.client_id = *client_id;
+                            player_map.0.remove(&old_client_id);
+                            watch.resume(old_client_id, *client_id);
+
+                            network.broadcast(&ServerMessage::DespawnPlayer {
+                                client_id: old_client_id,
+                            });
+                            info!("Player {old_client_id} resumed their session as {client_id}");
+                        }
+                        commands.entity(entity).remove::<DisconnectedPlayer>();
+
+                        let profile = profiles.get(entity).cloned().unwrap_or_default();
+                        (token, entity, profile)
+                    }
+                    None => {
+                        // Not drawn from GameRng, as tokens must not be predictable from the seed
+                        let token = rand::rng().random::<u64>();
+                        let mut profile = unique_profile(&connect_data.profile, profiles.iter());
+                        if match_rules.mode == GameMode::Teams {
+                            profile.team =
+                                Some(assign_team(connect_data.profile.team, profiles.iter()));
+                        }
+                        info!(
+                            "Player {client_id} joined as {:?} ({:?})",
+                            profile.name, profile.team
+                        );
+
+                        let entity = commands
+                            .spawn((
+                                Player {
+                                    client_id: *client_id,
+                                },
+                                profile.clone(),
+                                (Score::default(), Inventory::default()),
+                                SessionToken(token),
+                                ActiveEffects::default(),
+                                AbilityCooldowns::default(),
+                                RigidBody::Dynamic,
+                                Collider::cuboid(15.0, 15.0),
+                                Velocity::linear(Vec2::ZERO),
+                                PreStepVelocity::default(),
+                                Damping {
+                                    linear_damping: PLAYER_LINEAR_DAMPING,
+                                    angular_damping: 2.0,
+                                },
+                                Restitution::coefficient(collision_rules.bounce),
+                                ActiveEvents::COLLISION_EVENTS,
+                                Transform::from_translation(map.player_spawn.extend(0.0)),
+                                GlobalTransform::default(),
+                            ))
+                            .id();
+                        sessions.0.insert(token, entity);
+
+                        (token, entity, profile)
+                    }
                 };
-                let bytes =
-                    bincode::serde::encode_to_vec(&msg, bincode::config::standard()).unwrap();
-                server.send_message(*client_id, ServerChannel::World, bytes);
 
-                for player_client_id in player_map.0.keys() {
-                    let msg = ServerMessage::SpawnRemotePlayer {
-                        client_id: *player_client_id,
-                    };
-                    let bytes =
-                        bincode::serde::encode_to_vec(&msg, bincode::config::standard()).unwrap();
+                player_map.0.insert(*client_id, entity);
 
-                    server.broadcast_message(ServerChannel::World, bytes);
-                }
-            }
-            ServerEvent::ClientDisconnected { client_id, reason } => {
-                if let Some(entity) = player_map.0.remove(client_id) {
-                    commands.entity(entity).despawn();
+                network.send(*client_id, &ServerMessage::MapData(map.clone()));
+                network.send(
+                    *client_id,
+                    &ServerMessage::SpawnCollectibles(collectible_snapshot(&boxes)),
+                );
 
-                    let msg = ServerMessage::DespawnPlayer {
+                network.send(
+                    *client_id,
+                    &ServerMessage::AssignClientId {
                         client_id: *client_id,
+                    },
+                );
+
+                network.send(*client_id, &ServerMessage::SessionToken { token });
+
+                for (player_client_id, player_entity) in player_map.0.iter() {
+                    // The new player's entity may not exist yet, so use its profile directly
+                    let profile = if *player_entity == entity {
+                        profile.clone()
+                    } else {
+                        profiles.get(*player_entity).cloned().unwrap_or_default()
                     };
-                    let bytes =
-                        bincode::serde::encode_to_vec(&msg, bincode::config::standard()).unwrap();
 
-                    server.broadcast_message(ServerChannel::World, bytes);
-                    info!("Despawned disconnected player {client_id} ({reason:?})");
+                    network.broadcast(&ServerMessage::SpawnRemotePlayer {
+                        client_id: *player_client_id,
+                        profile,
+                    });
+
+                    // Bring the newcomer up to date; a resumed player gets their own score back
+                    let status = statuses.get(*player_entity).ok();
+                    send_player_status(
+                        &mut network,
+                        *client_id,
+                        *player_client_id,
+                        status.map(|(score, _)| score),
+                        status.map(|(_, effects)| effects),
+                    );
+                }
+            }
+            ServerEvent::ClientDisconnected { client_id, reason } => {
+                // Keep the player around in case the client comes back with its token
+                if let Some(entity) = player_map.0.get(client_id) {
+                    commands.entity(*entity).insert((
+                        DisconnectedPlayer {
+                            timer: Timer::new(config.reconnect_grace, TimerMode::Once),
+                        },
+                        Velocity::zero(),
+                    ));
+                    info!(
+                        "Player {client_id} disconnected ({reason:?}), holding for {:?}",
+                        config.reconnect_grace
+                    );
                 }
             }
         }
     }
 }
 
+fn expire_disconnected_players(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut network: ServerNetwork,
+    mut player_map: ResMut<PlayerEntityMap>,
+    mut sessions: ResMut<SessionMap>,
+    mut players: Query<(Entity, &Player, &SessionToken, &mut DisconnectedPlayer)>,
+) {
+    for (entity, player, token, mut disconnected) in players.iter_mut() {
+        if !disconnected.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        drop_player(
+            &mut commands,
+            &mut network,
+            &mut player_map,
+            &mut sessions,
+            (entity, player, token),
+        );
+        info!(
+            "Despawned disconnected player {} after grace period",
+            player.client_id
+        );
+    }
+}
+
+/// Despawns a player for everyone and forgets its session, so it can't be resumed
+pub fn drop_player(
+    commands: &mut Commands,
+    network: &mut ServerNetwork,
+    player_map: &mut PlayerEntityMap,
+    sessions: &mut SessionMap,
+    (entity, player, token): (Entity, &Player, &SessionToken),
+) {
+    commands.entity(entity).despawn();
+    player_map.0.remove(&player.client_id);
+    sessions.0.remove(&token.0);
+
+    network.broadcast(&ServerMessage::DespawnPlayer {
+        client_id: player.client_id,
+    });
+}
+
 // === Print Events (Optional) ===
 fn print_server_events(mut events: EventReader<ServerEvent>) {
     for event in events.read() {
@@ -202,21 +605,45 @@ fn print_server_events(mut events: EventReader<ServerEvent>) {
 }
 
 // === Main Receive Logic ===
+#[allow(
+    clippy::too_many_arguments,
+    clippy::type_complexity,
+    reason = "dispatches every kind of client message"
+)]
 fn receive_from_clients(
     mut commands: Commands,
-    mut server: ResMut<RenetServer>,
+    mut network: ServerNetwork,
+    time: Res<Time>,
     player_map: Res<PlayerEntityMap>,
+    match_state: Res<MatchState>,
+    match_rules: Res<MatchRules>,
+    history: Res<PositionHistory>,
+    mut ids: ResMut<NetworkIds>,
     mut collectible_entities: ResMut<CollectibleEntityMap>,
-    mut transforms: Query<&mut Velocity, With<Player>>,
+    mut players: Query<
+        (
+            &mut Velocity,
+            &mut Score,
+            &mut ActiveEffects,
+            &mut AbilityCooldowns,
+            &mut ExternalImpulse,
+            &ReadMassProperties,
+            &mut Inventory,
+        ),
+        With<Player>,
+    >,
+    positions: Query<&Transform>,
+    kinds: Query<&CollectibleKind>,
+    states: Query<(Has<Stunned>, Has<Reloading>)>,
+    mut chat_events: EventWriter<ChatReceived>,
+    mut flood: FloodGuard,
+    mut watch: ResMut<CheatWatch>,
 ) {
-    for client_id in server.clients_id() {
-        while let Some(message) = server.receive_message(client_id, 0u8) {
-            let Ok((msg, _)) = bincode::serde::decode_from_slice::<ClientMessage, _>(
-                &message,
-                bincode::config::standard(),
-            ) else {
+    for client_id in network.clients_id() {
+        while let Some(msg) = network.receive(client_id) {
+            if !flood.allow(&mut network, client_id, &msg) {
                 continue;
-            };
+            }
 
             match msg {
                 ClientMessage::MoveInput {
@@ -224,71 +651,162 @@ fn receive_from_clients(
                     frame: _frame, //unused
                     delta,
                 } => {
+                    if !delta.is_finite() || !direction.is_finite() {
+                        continue;
+                    }
+                    watch.record_input(client_id, delta, time.elapsed_secs());
+                    // A client claiming more time than has passed would get a bigger push, so
+                    // the anti-cheat flags it and this stops it from counting: no input covers
+                    // more than one slice, and all of them no more than the time gone by
+                    let delta =
+                        flood.movement_time(client_id, delta.clamp(0.0, MAX_INPUT_DELTA_SECS));
                     if let Some(entity) = player_map.0.get(&client_id) {
-                        if let Ok(mut velocity) = transforms.get_mut(*entity) {
+                        if states.get(*entity).is_ok_and(|(stunned, _)| stunned) {
+                            continue;
+                        }
+                        if let Ok((mut velocity, _, effects, .., inventory)) =
+                            players.get_mut(*entity)
+                        {
                             let dir = direction.clamp_length_max(1.0);
-                            velocity.linvel += dir * MAX_ACCELERATION * delta;
+                            let acceleration = MAX_ACCELERATION
+                                * effects.acceleration_multiplier()
+                                * inventory.acceleration_multiplier();
+                            velocity.linvel += dir * acceleration * delta;
                         }
                     }
                 }
 
                 ClientMessage::AttemptCollect { id } => {
+                    if !match_state.allows_collecting() {
+                        continue;
+                    }
+                    let Some(player_entity) = player_map.0.get(&client_id) else {
+                        continue;
+                    };
+
                     if let Some(entity) = collectible_entities.0.get(&id) {
-                        commands.entity(*entity).despawn();
-                        collectible_entities.0.remove(&id);
+                        let in_reach = positions
+                            .get(*player_entity)
+                            .ok()
+                            .zip(positions.get(*entity).ok())
+                            .is_some_and(|(player, collectible)| {
+                                player.translation.distance(collectible.translation)
+                                    <= COLLECT_RADIUS + COLLECT_TOLERANCE
+                            });
+                        if !in_reach {
+                            watch.record_collect_miss(client_id, time.elapsed_secs());
+                            continue;
+                        }
 
-                        let msg = ServerMessage::DespawnCollectible { id };
-                        let bytes =
-                            bincode::serde::encode_to_vec(&msg, bincode::config::standard())
-                                .unwrap();
-                        server.broadcast_message(ServerChannel::World, bytes);
+                        let kind = kinds
+                            .get(*entity)
+                            .copied()
+                            .unwrap_or(CollectibleKind::Points(1));
+                        let carries = kind.points() > 0 && match_rules.carries();
+                        if let Ok((_, mut score, mut effects, .., mut inventory)) =
+                            players.get_mut(*player_entity)
+                        {
+                            // Boxes that would go over the weight limit stay where they are
+                            if carries && !inventory.fits(kind) {
+                                continue;
+                            }
 
-                        // Spawn a new collectable now
-                        let mut rng = rand::rng();
+                            // In team and delivery mode points only count once dropped off
+                            if carries {
+                                inventory.0.push(kind);
+                                network.broadcast(&inventory.message(client_id));
+                            } else if kind.points() > 0 {
+                                score.0 += kind.points();
+                                network.broadcast(&ServerMessage::ScoreUpdate {
+                                    client_id,
+                                    score: score.0,
+                                });
+                            }
 
-                        let id = random_u64();
-                        let position = Vec3::new(
-                            rng.random_range(-600.0..=600.0),
-                            rng.random_range(-300.0..=300.0),
-                            0.0,
-                        );
+                            if let CollectibleKind::PowerUp(power_up) = kind {
+                                apply_power_up(
+                                    &mut commands,
+                                    &mut network,
+                                    *player_entity,
+                                    client_id,
+                                    &mut effects,
+                                    power_up,
+                                );
+                            }
+                        }
 
-                        let entity = commands
-                            .spawn((
-                                BoxCollectable,
-                                CollectibleId(id),
-                                Transform::from_translation(position),
-                                Sprite {
-                                    color: YELLOW.into(),
-                                    custom_size: Some(Vec2::splat(20.0)),
-                                    ..default()
-                                },
-                            ))
-                            .id();
+                        commands.entity(*entity).despawn();
+                        collectible_entities.0.remove(&id);
 
-                        collectible_entities.0.insert(id, entity);
+                        // The spawner replaces it after the map's respawn delay
+                        network.broadcast(&ServerMessage::DespawnCollectible { id });
+                    }
+                }
 
-                        let snapshot: Vec<CollectibleInfo> = vec![CollectibleInfo {
-                            id: id,
-                            position: position,
-                        }];
+                ClientMessage::UseAbility { ability, direction } => {
+                    let Some(entity) = player_map.0.get(&client_id) else {
+                        continue;
+                    };
+                    if states.get(*entity).is_ok_and(|(stunned, _)| stunned) {
+                        continue;
+                    }
+                    if let Ok((_, _, _, mut cooldowns, mut impulse, mass, _)) =
+                        players.get_mut(*entity)
+                    {
+                        use_ability(
+                            &mut network,
+                            client_id,
+                            &mut cooldowns,
+                            &mut impulse,
+                            mass,
+                            ability,
+                            direction,
+                        );
+                    }
+                }
 
-                        let msg = ServerMessage::SpawnCollectibles(snapshot);
-                        let bytes =
-                            bincode::serde::encode_to_vec(&msg, bincode::config::standard())
-                                .unwrap();
-                        server.broadcast_message(ServerChannel::World, bytes);
+                ClientMessage::Fire { direction, frame } => {
+                    let Some(entity) = player_map.0.get(&client_id) else {
+                        continue;
+                    };
+                    let ready = states
+                        .get(*entity)
+                        .is_ok_and(|(stunned, reloading)| !stunned && !reloading);
+                    let Ok(transform) = positions.get(*entity) else {
+                        continue;
+                    };
+                    if ready {
+                        fire(
+                            &mut commands,
+                            &mut network,
+                            &mut ids,
+                            &history,
+                            time.elapsed_secs(),
+                            *entity,
+                            client_id,
+                            transform.translation,
+                            direction,
+                            frame,
+                        );
                     }
                 }
+
+                // Checked and passed on by the chat plugin
+                ClientMessage::Chat { text } => {
+                    chat_events.write(ChatReceived { client_id, text });
+                }
             }
         }
     }
 }
 
 fn broadcast_player_positions(
-    players: Query<(&Player, &Transform)>,
-    mut server: ResMut<RenetServer>,
+    players: Query<(Entity, &Player, &Transform, &Velocity)>,
+    time: Res<Time>,
+    tick: Res<ServerTick>,
+    mut network: ServerNetwork,
     mut last_player_position: ResMut<LastPlayerPosition>,
+    mut history: ResMut<PositionHistory>,
 ) {
     if std::time::Instant::now()
         .duration_since(last_player_position.time)
@@ -297,21 +815,57 @@ fn broadcast_player_positions(
         last_player_position.time = std::time::Instant::now();
         let player_positions = players
             .iter()
-            .map(|(player, transform)| PositionData {
+            .map(|(_, player, transform, velocity)| PositionData {
                 client_id: player.client_id,
                 position: transform.translation,
                 rotation: transform.rotation,
+                velocity: velocity.linvel,
             })
             .collect::<Vec<_>>();
 
-        let msg = ServerMessage::PlayerPositions(player_positions);
+        // Remember what clients were shown, so shots can be checked against it later
+        history.record(Snapshot {
+            tick: tick.0,
+            time: time.elapsed_secs(),
+            positions: players
+                .iter()
+                .map(|(entity, _, transform, _)| (entity, transform.translation.truncate()))
+                .collect(),
+        });
 
-        let bytes = bincode::serde::encode_to_vec(&msg, bincode::config::standard()).unwrap();
-        server.broadcast_message(ServerChannel::World, bytes);
+        network.broadcast(&ServerMessage::PlayerPositions {
+            tick: tick.0,
+            players: player_positions,
+        });
     }
 }
 
 // === Components and Resources ===
+/// Options passed on the command line after `server`
+#[derive(Resource)]
+pub struct ServerConfig {
+    /// Write every accepted and emitted message to this file
+    pub record: Option<String>,
+    /// How long a disconnected player is kept around waiting for the client to resume
+    pub reconnect_grace: Duration,
+}
+
+impl ServerConfig {
+    /// Fails if `--grace` isn't a non-negative number of seconds
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let reconnect_grace = match arg_value(args, "--grace") {
+            Some(value) => parse_secs(value)
+                .ok_or_else(|| format!("Usage: --grace <seconds>, got {value:?}"))?,
+            None => DEFAULT_RECONNECT_GRACE,
+        };
+
+        Ok(Self {
+            record: arg_value(args, "--record").map(String::from),
+            reconnect_grace,
+        })
+    }
+}
+
 #[derive(Component)]
 pub struct CollectibleId(pub u64);
 
@@ -326,6 +880,86 @@ pub struct Player {
 #[derive(Resource, Default)]
 pub struct PlayerEntityMap(pub HashMap<u64, Entity>);
 
+/// Number of boxes a player has collected
+#[derive(Component, Default)]
+pub struct Score(pub u32);
+
+/// Secret handed to a client so it can reclaim its player after a disconnect
+#[derive(Component)]
+pub struct SessionToken(pub u64);
+
+/// Maps session tokens to the player entity they resume
+#[derive(Resource, Default)]
+pub struct SessionMap(pub HashMap<u64, Entity>);
+
+/// Marks a player whose client dropped; despawned when the timer runs out
+#[derive(Component)]
+pub struct DisconnectedPlayer {
+    pub timer: Timer,
+}
+
+/// A wall or obstacle collider, replaced when the map changes
+#[derive(Component)]
+pub struct MapSolid;
+
+/// Frames per second the server runs at, set with `--tick-rate` or the `tickrate` admin command
+#[derive(Resource)]
+pub struct TickRate(pub u32);
+
+impl TickRate {
+    pub fn from_args(args: &[String]) -> Self {
+        Self(
+            arg_value(args, "--tick-rate")
+                .and_then(|rate| rate.parse().ok())
+                .filter(|rate| *rate > 0)
+                .unwrap_or(DEFAULT_TICK_RATE),
+        )
+    }
+}
+
+/// Number of frames the server has run, used to timestamp recorded packets
+#[derive(Resource, Default)]
+pub struct ServerTick(pub u64);
+
+/// Hands out IDs for networked entities such as collectibles. IDs count up from 1 and are
+/// never reused within a session.
+#[derive(Resource, Default)]
+pub struct NetworkIds {
+    last: u64,
+}
+
+impl NetworkIds {
+    pub fn allocate(&mut self) -> u64 {
+        self.last += 1;
+        self.last
+    }
+}
+
+/// Source of all gameplay randomness, so a session can be reproduced with `--seed`
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng {
+    pub seed: u64,
+    #[deref]
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn from_args(args: &[String]) -> Self {
+        let seed = arg_value(args, "--seed")
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or_else(|| rand::rng().random());
+
+        Self::new(seed)
+    }
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct LastPlayerPosition {
     pub time: std::time::Instant,
@@ -340,13 +974,172 @@ impl Default for LastPlayerPosition {
 }
 
 // ===== Utils ======
-fn random_u64() -> u64 {
-    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+/// Every collectible currently in play, as sent to joining clients
+pub fn collectible_snapshot(
+    boxes: &Query<(&CollectibleId, &CollectibleKind, &Transform), With<BoxCollectable>>,
+) -> Vec<CollectibleInfo> {
+    boxes
+        .iter()
+        .map(|(id, kind, t)| CollectibleInfo {
+            id: id.0,
+            position: t.translation,
+            kind: *kind,
+        })
+        .collect()
+}
 
-    let seed = now.as_secs() ^ now.subsec_nanos() as u64;
+/// Tells `to` a player's score and the power-ups they have running
+pub fn send_player_status(
+    network: &mut ServerNetwork,
+    to: u64,
+    client_id: u64,
+    score: Option<&Score>,
+    effects: Option<&ActiveEffects>,
+) {
+    if let Some(score) = score {
+        network.send(
+            to,
+            &ServerMessage::ScoreUpdate {
+                client_id,
+                score: score.0,
+            },
+        );
+    }
+
+    for (power_up, timer) in effects.into_iter().flat_map(|effects| &effects.0) {
+        network.send(
+            to,
+            &ServerMessage::EffectStarted {
+                client_id,
+                power_up: *power_up,
+                remaining_secs: timer.remaining_secs(),
+            },
+        );
+    }
+}
+
+/// Cleans up a requested profile and makes its name and color unique among `taken`
+pub fn unique_profile<'a>(
+    requested: &PlayerProfile,
+    taken: impl Iterator<Item = &'a PlayerProfile> + Clone,
+) -> PlayerProfile {
+    let base: String = requested
+        .name
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_LEN)
+        .collect::<String>()
+        .trim()
+        .to_string();
+    let base = if base.is_empty() {
+        "Player".to_string()
+    } else {
+        base
+    };
+
+    // The base is shortened where needed so the numbered name still fits
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while taken.clone().any(|profile| profile.name == name) {
+        let number = format!(" {suffix}");
+        let room = MAX_NAME_LEN.saturating_sub(number.chars().count());
+        let shortened: String = base.chars().take(room).collect();
+        name = format!("{}{number}", shortened.trim_end());
+        suffix += 1;
+    }
+
+    // Fall back to the first free color, or keep the request if every color is in use
+    let requested_color = requested.color % PLAYER_COLORS.len() as u8;
+    let color = std::iter::once(requested_color)
+        .chain(0..PLAYER_COLORS.len() as u8)
+        .find(|color| !taken.clone().any(|profile| profile.color == *color))
+        .unwrap_or(requested_color);
+
+    PlayerProfile {
+        name,
+        color,
+        team: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    const A: u64 = u64::MAX;
-    const C: u64 = u64::MIN;
+    fn profile(name: &str, color: u8) -> PlayerProfile {
+        PlayerProfile {
+            name: name.to_string(),
+            color,
+            team: None,
+        }
+    }
+
+    #[test]
+    fn free_profile_is_kept() {
+        let taken = [profile("Alice", 1)];
+        assert_eq!(
+            unique_profile(&profile("Bob", 2), taken.iter()),
+            profile("Bob", 2)
+        );
+    }
 
-    A.wrapping_mul(seed).wrapping_add(C)
+    #[test]
+    fn taken_names_get_a_number() {
+        let taken = [profile("Bob", 0), profile("Bob 2", 1)];
+        assert_eq!(
+            unique_profile(&profile("Bob", 5), taken.iter()).name,
+            "Bob 3"
+        );
+    }
+
+    #[test]
+    fn numbered_names_stay_within_the_limit() {
+        let long = "x".repeat(MAX_NAME_LEN);
+        let taken = [profile(&long, 0)];
+        let name = unique_profile(&profile(&long, 1), taken.iter()).name;
+        assert_eq!(name.chars().count(), MAX_NAME_LEN);
+        assert!(name.ends_with(" 2"));
+    }
+
+    #[test]
+    fn names_are_cleaned_up() {
+        let none = std::iter::empty();
+        assert_eq!(
+            unique_profile(&profile("  \tBob\n ", 0), none.clone()).name,
+            "Bob"
+        );
+        assert_eq!(
+            unique_profile(&profile(" \u{7} ", 0), none.clone()).name,
+            "Player"
+        );
+        let long = "x".repeat(MAX_NAME_LEN * 2);
+        assert_eq!(
+            unique_profile(&profile(&long, 0), none)
+                .name
+                .chars()
+                .count(),
+            MAX_NAME_LEN
+        );
+    }
+
+    #[test]
+    fn taken_colors_fall_back_to_the_first_free_one() {
+        let taken = [profile("Alice", 0), profile("Bob", 1)];
+        assert_eq!(unique_profile(&profile("Carol", 0), taken.iter()).color, 2);
+
+        // Out of range colors wrap around first
+        let wrapped = PLAYER_COLORS.len() as u8 + 3;
+        assert_eq!(
+            unique_profile(&profile("Carol", wrapped), taken.iter()).color,
+            3
+        );
+
+        let everyone: Vec<PlayerProfile> = (0..PLAYER_COLORS.len() as u8)
+            .map(|color| profile(&format!("P{color}"), color))
+            .collect();
+        assert_eq!(
+            unique_profile(&profile("Carol", 4), everyone.iter()).color,
+            4
+        );
+    }
 }
@@ -0,0 +1,149 @@
+use crate::{
+    chat_box::typing,
+    client::{ClientInfo, ClientNetwork, Player, RemotePlayer},
+    controls::{Action, PlayerInput},
+    protocol::{ClientMessage, FIRE_COOLDOWN_SECS, PROJECTILE_RADIUS, ProjectileInfo},
+    screens::ClientState,
+};
+use bevy::{prelude::*, window::PrimaryWindow};
+
+/// Seconds a hit flash stays on the player that was hit
+const HIT_FLASH_SECS: f32 = 0.25;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Stun>()
+        .add_systems(OnEnter(ClientState::Connecting), clear_stun)
+        .add_systems(
+            Update,
+            (
+                tick_stun,
+                fire.run_if(not(typing)),
+                move_projectiles,
+                fade_hit_flashes,
+            )
+                .run_if(in_state(ClientState::InGame)),
+        );
+}
+
+// === Shooting ===
+fn clear_stun(mut stun: ResMut<Stun>) {
+    stun.0 = 0.0;
+}
+
+fn tick_stun(time: Res<Time>, mut stun: ResMut<Stun>) {
+    stun.0 = (stun.0 - time.delta_secs()).max(0.0);
+}
+
+/// Fire shoots towards the cursor, or where the right stick points. The server spawns the
+/// projectile; we just tell it which player positions we were looking at.
+#[allow(
+    clippy::too_many_arguments,
+    reason = "aiming needs the window, camera and player on top of the input"
+)]
+fn fire(
+    input: Res<PlayerInput>,
+    time: Res<Time>,
+    stun: Res<Stun>,
+    client_info: Res<ClientInfo>,
+    mut reload: Local<f32>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    local_player: Query<&Transform, (With<Player>, Without<RemotePlayer>)>,
+    mut network: ClientNetwork,
+) {
+    *reload = (*reload - time.delta_secs()).max(0.0);
+    if !input.just_pressed(Action::Fire) || *reload > 0.0 || stun.active() {
+        return;
+    }
+
+    let Ok(player) = local_player.single() else {
+        return;
+    };
+    let position = player.translation.truncate();
+    // A pushed right stick aims; otherwise we fire towards the cursor
+    let direction = input.aim.or_else(|| {
+        let (window, (camera, camera_transform)) = (windows.single().ok()?, camera.single().ok()?);
+        let cursor = window.cursor_position()?;
+        let target = camera.viewport_to_world_2d(camera_transform, cursor).ok()?;
+        Some((target - position).normalize_or_zero())
+    });
+    let Some(direction) = direction.filter(|direction| *direction != Vec2::ZERO) else {
+        return;
+    };
+
+    *reload = FIRE_COOLDOWN_SECS;
+    network.send(&ClientMessage::Fire {
+        direction,
+        frame: client_info.snapshot_tick,
+    });
+}
+
+fn move_projectiles(time: Res<Time>, mut projectiles: Query<(&RemoteProjectile, &mut Transform)>) {
+    for (projectile, mut transform) in projectiles.iter_mut() {
+        transform.translation += projectile.velocity.extend(0.0) * time.delta_secs();
+    }
+}
+
+fn fade_hit_flashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flashes: Query<(Entity, &mut HitFlash, &mut Sprite)>,
+) {
+    for (entity, mut flash, mut sprite) in flashes.iter_mut() {
+        if flash.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        } else {
+            sprite.color.set_alpha(0.8 * flash.0.fraction_remaining());
+        }
+    }
+}
+
+/// A projectile as spawned by the server
+pub fn projectile(info: &ProjectileInfo) -> impl Bundle {
+    (
+        StateScoped(ClientState::InGame),
+        RemoteProjectile {
+            id: info.id,
+            velocity: info.velocity,
+        },
+        Sprite {
+            color: Color::WHITE,
+            custom_size: Some(Vec2::splat(PROJECTILE_RADIUS * 2.0)),
+            ..default()
+        },
+        Transform::from_translation(info.position),
+    )
+}
+
+/// Briefly drawn over a player that was hit; spawn it as their child
+pub fn hit_flash() -> impl Bundle {
+    (
+        HitFlash(Timer::from_seconds(HIT_FLASH_SECS, TimerMode::Once)),
+        Sprite {
+            color: Color::WHITE,
+            custom_size: Some(Vec2::splat(34.0)),
+            ..default()
+        },
+        Transform::from_xyz(0.0, 0.0, 0.5),
+    )
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+pub struct RemoteProjectile {
+    pub id: u64,
+    velocity: Vec2,
+}
+
+#[derive(Component)]
+struct HitFlash(Timer);
+
+/// Seconds until we can move again after being hit
+#[derive(Resource, Default)]
+pub struct Stun(pub f32);
+
+impl Stun {
+    pub fn active(&self) -> bool {
+        self.0 > 0.0
+    }
+}
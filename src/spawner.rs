@@ -0,0 +1,241 @@
+use crate::{
+    BoxCollectable, CollectibleInfo, ServerMessage,
+    map::MapDefinition,
+    protocol::CollectibleKind,
+    server::{
+        CollectibleEntityMap, CollectibleId, GameRng, NetworkIds, Player, ServerNetwork,
+        spawn_collectible,
+    },
+};
+use bevy::prelude::*;
+use rand::{Rng, seq::IndexedRandom};
+
+/// Random spots tried per box before waiting for the next frame
+const MAX_PLACEMENT_ATTEMPTS: usize = 20;
+
+/// Keeps boxes from poking into walls and obstacles
+const OBSTACLE_MARGIN: f32 = 10.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, maintain_collectibles);
+}
+
+// === Spawning ===
+/// Tops the world back up to the map's target count, waiting out the respawn delay first
+#[allow(
+    clippy::too_many_arguments,
+    reason = "placing a box checks players, boxes and the map"
+)]
+fn maintain_collectibles(
+    mut commands: Commands,
+    time: Res<Time>,
+    map: Res<MapDefinition>,
+    mut spawner: ResMut<CollectibleSpawner>,
+    mut collectible_entities: ResMut<CollectibleEntityMap>,
+    mut ids: ResMut<NetworkIds>,
+    mut rng: ResMut<GameRng>,
+    mut network: ServerNetwork,
+    boxes: Query<&Transform, With<BoxCollectable>>,
+    players: Query<&Transform, With<Player>>,
+) {
+    let rules = &map.collectibles;
+    let respawns = &mut spawner.respawns;
+
+    let missing = rules
+        .target_count
+        .saturating_sub(collectible_entities.0.len() + respawns.len());
+    for _ in 0..missing {
+        respawns.push(Timer::from_seconds(
+            rules.respawn_delay_secs,
+            TimerMode::Once,
+        ));
+    }
+
+    for timer in respawns.iter_mut() {
+        timer.tick(time.delta());
+    }
+
+    let mut taken: Vec<Vec2> = boxes
+        .iter()
+        .chain(players.iter())
+        .map(|transform| transform.translation.truncate())
+        .collect();
+    let mut spawned = Vec::new();
+
+    respawns.retain(|timer| {
+        if !timer.finished() {
+            return true;
+        }
+
+        // No room right now; keep the respawn queued and try again next frame
+        let Some(position) = pick_position(&mut **rng, &map, &taken) else {
+            return true;
+        };
+
+        taken.push(position);
+        spawned.push(spawn_collectible(
+            &mut commands,
+            &mut collectible_entities,
+            &mut ids,
+            position.extend(0.0),
+            pick_kind(&mut **rng, &map),
+        ));
+        false
+    });
+
+    if !spawned.is_empty() {
+        network.broadcast(&ServerMessage::SpawnCollectibles(spawned));
+    }
+}
+
+/// Fills the world up to the target count straight away, e.g. at the start of a round.
+/// Anything queued to respawn is dropped.
+pub fn spawn_initial_collectibles(
+    commands: &mut Commands,
+    collectible_entities: &mut CollectibleEntityMap,
+    spawner: &mut CollectibleSpawner,
+    ids: &mut NetworkIds,
+    rng: &mut GameRng,
+    map: &MapDefinition,
+) -> Vec<CollectibleInfo> {
+    spawner.respawns.clear();
+
+    let mut taken = vec![map.player_spawn];
+    let mut spawned = Vec::new();
+
+    for _ in 0..map.collectibles.target_count {
+        let Some(position) = pick_position(&mut **rng, map, &taken) else {
+            // Whatever didn't fit is picked up by the respawn queue
+            break;
+        };
+
+        taken.push(position);
+        spawned.push(spawn_collectible(
+            commands,
+            collectible_entities,
+            ids,
+            position.extend(0.0),
+            pick_kind(&mut **rng, map),
+        ));
+    }
+
+    spawned
+}
+
+/// Clears away every box and lays out a fresh set, telling clients about both
+#[allow(
+    clippy::too_many_arguments,
+    reason = "called from systems that already hold each of these"
+)]
+pub fn respawn_collectibles(
+    commands: &mut Commands,
+    network: &mut ServerNetwork,
+    collectible_entities: &mut CollectibleEntityMap,
+    spawner: &mut CollectibleSpawner,
+    ids: &mut NetworkIds,
+    rng: &mut GameRng,
+    map: &MapDefinition,
+    boxes: &Query<(Entity, &CollectibleId), With<BoxCollectable>>,
+) {
+    for (entity, id) in boxes.iter() {
+        network.broadcast(&ServerMessage::DespawnCollectible { id: id.0 });
+        commands.entity(entity).despawn();
+    }
+    collectible_entities.0.clear();
+
+    let spawned =
+        spawn_initial_collectibles(commands, collectible_entities, spawner, ids, rng, map);
+    network.broadcast(&ServerMessage::SpawnCollectibles(spawned));
+}
+
+/// A random spot in one of the spawn zones that is clear of solids and at least
+/// `min_spacing` away from everything in `taken`
+fn pick_position(rng: &mut impl Rng, map: &MapDefinition, taken: &[Vec2]) -> Option<Vec2> {
+    let min_spacing = map.collectibles.min_spacing;
+
+    (0..MAX_PLACEMENT_ATTEMPTS)
+        .map(|_| map.random_spawn_point(rng))
+        .find(|point| {
+            taken
+                .iter()
+                .all(|other| other.distance(*point) >= min_spacing)
+                && !map
+                    .solids()
+                    .any(|solid| solid.contains(*point, OBSTACLE_MARGIN))
+        })
+}
+
+/// A kind of collectible, chosen by the map's weights
+fn pick_kind(rng: &mut impl Rng, map: &MapDefinition) -> CollectibleKind {
+    map.collectibles
+        .kinds
+        .choose_weighted(rng, |(_, weight)| *weight)
+        .map(|(kind, _)| *kind)
+        .unwrap_or(CollectibleKind::Points(1))
+}
+
+// === Components and Resources ===
+#[derive(Resource, Default)]
+pub struct CollectibleSpawner {
+    /// One timer per box waiting to come back
+    respawns: Vec<Timer>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::DEFAULT_MAP;
+    use bevy::ecs::world::CommandQueue;
+
+    /// Lays out two rounds of collectibles in a fresh world seeded with `seed`
+    fn play_rounds(seed: u64) -> Vec<(u64, Vec3, CollectibleKind)> {
+        let map = MapDefinition::load(DEFAULT_MAP).unwrap();
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        let mut collectible_entities = CollectibleEntityMap::default();
+        let mut spawner = CollectibleSpawner::default();
+        let mut ids = NetworkIds::default();
+        let mut rng = GameRng::new(seed);
+
+        let mut spawned = Vec::new();
+        for _ in 0..2 {
+            spawned.extend(spawn_initial_collectibles(
+                &mut commands,
+                &mut collectible_entities,
+                &mut spawner,
+                &mut ids,
+                &mut rng,
+                &map,
+            ));
+        }
+        queue.apply(&mut world);
+
+        spawned
+            .into_iter()
+            .map(|info| (info.id, info.position, info.kind))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_spawns_the_same_world() {
+        let first = play_rounds(7);
+        assert!(!first.is_empty());
+        assert_eq!(first, play_rounds(7));
+
+        let ids: Vec<u64> = first.iter().map(|(id, ..)| *id).collect();
+        let expected: Vec<u64> = (1..=first.len() as u64).collect();
+        assert_eq!(ids, expected, "ids count up from 1 without reuse");
+    }
+
+    #[test]
+    fn different_seeds_diverge() {
+        let positions = |seed| -> Vec<Vec3> {
+            play_rounds(seed)
+                .into_iter()
+                .map(|(_, position, _)| position)
+                .collect()
+        };
+        assert_ne!(positions(7), positions(8));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    camera::{CameraZoom, clamp_to_map, follow_blend},
+    chat_box::typing,
+    client::{ClientSettings, RemotePlayer},
+    controls::PlayerInput,
+    map::MapDefinition,
+    scoreboard::Scoreboard,
+    screens::ClientState,
+};
+use bevy::prelude::*;
+
+/// World units per second the free camera pans at, before zoom
+const PAN_SPEED: f32 = 600.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SpectatorCamera>()
+        .add_systems(
+            OnEnter(ClientState::InGame),
+            (reset_spectator_camera, spawn_spectator_hud).run_if(spectating),
+        )
+        .add_systems(
+            Update,
+            (
+                control_spectator_camera.run_if(not(typing)),
+                move_spectator_camera,
+                update_spectator_hud,
+            )
+                .chain()
+                .run_if(in_state(ClientState::InGame).and(spectating)),
+        );
+}
+
+/// Run condition for clients watching without a player of their own
+pub fn spectating(settings: Res<ClientSettings>) -> bool {
+    settings.spectate
+}
+
+// === Spectator Camera ===
+fn reset_spectator_camera(mut camera: ResMut<SpectatorCamera>) {
+    *camera = SpectatorCamera::default();
+}
+
+/// The movement bindings pan freely, and Q and E follow the previous or next player
+fn control_spectator_camera(
+    keys: Res<ButtonInput<KeyCode>>,
+    input: Res<PlayerInput>,
+    time: Res<Time>,
+    zoom: Res<CameraZoom>,
+    mut camera: ResMut<SpectatorCamera>,
+    players: Query<&RemotePlayer>,
+    view: Query<&Transform, With<Camera2d>>,
+) {
+    let step = match (
+        keys.just_pressed(KeyCode::KeyE),
+        keys.just_pressed(KeyCode::KeyQ),
+    ) {
+        (true, false) => Some(1),
+        (false, true) => Some(-1),
+        _ => None,
+    };
+    if let Some(step) = step {
+        let mut ids: Vec<u64> = players.iter().map(|player| player.client_id).collect();
+        ids.sort();
+        camera.following = cycle(&ids, camera.following, step);
+    }
+
+    let pan = input.movement;
+    if pan == Vec2::ZERO {
+        return;
+    }
+
+    // Panning lets go of whoever we were following, starting from where the view is now
+    let was_following = camera.following.take().is_some();
+    if let (true, Ok(transform)) = (was_following, view.single()) {
+        camera.position = transform.translation.truncate();
+    }
+    camera.position += pan * PAN_SPEED * zoom.0 * time.delta_secs();
+}
+
+/// The id `step` places along from `current` in `ids`, wrapping around
+fn cycle(ids: &[u64], current: Option<u64>, step: isize) -> Option<u64> {
+    if ids.is_empty() {
+        return None;
+    }
+    let index = match current.and_then(|id| ids.iter().position(|other| *other == id)) {
+        Some(index) => (index as isize + step).rem_euclid(ids.len() as isize) as usize,
+        None if step > 0 => 0,
+        None => ids.len() - 1,
+    };
+    Some(ids[index])
+}
+
+fn move_spectator_camera(
+    time: Res<Time>,
+    zoom: Res<CameraZoom>,
+    map: Option<Res<MapDefinition>>,
+    mut camera: ResMut<SpectatorCamera>,
+    players: Query<(&RemotePlayer, &Transform), Without<Camera2d>>,
+    mut view: Query<(&Camera, &mut Transform), With<Camera2d>>,
+) {
+    let Ok((view_camera, mut transform)) = view.single_mut() else {
+        return;
+    };
+
+    if let Some(id) = camera.following {
+        match players.iter().find(|(player, _)| player.client_id == id) {
+            Some((_, target)) => camera.position = target.translation.truncate(),
+            // They left, so stay put with the free camera
+            None => camera.following = None,
+        }
+    }
+
+    camera.position = clamp_to_map(camera.position, view_camera, zoom.0, map.as_deref());
+    let current = transform.translation.truncate();
+    transform.translation = current
+        .lerp(camera.position, follow_blend(&time))
+        .extend(transform.translation.z);
+}
+
+fn spawn_spectator_hud(mut commands: Commands) {
+    commands.spawn((
+        StateScoped(ClientState::InGame),
+        SpectatorHud,
+        Text::default(),
+        TextFont {
+            font_size: 18.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+    ));
+}
+
+fn update_spectator_hud(
+    camera: Res<SpectatorCamera>,
+    scoreboard: Res<Scoreboard>,
+    mut hud: Query<&mut Text, With<SpectatorHud>>,
+) {
+    let Ok(mut text) = hud.single_mut() else {
+        return;
+    };
+
+    let following = camera
+        .following
+        .and_then(|id| scoreboard.entries.get(&id))
+        .map(|entry| entry.name.as_str());
+    let status = match following {
+        Some(name) => format!("Spectating {name}. Q/E: switch player, move keys: free camera"),
+        None => "Spectating. Move keys: pan, Q/E: follow a player".to_string(),
+    };
+    let status = format!("{status}, mouse wheel or -/=: zoom");
+    if text.0 != status {
+        text.0 = status;
+    }
+}
+
+// === Components and Resources ===
+#[derive(Component)]
+struct SpectatorHud;
+
+/// Where the spectator wants the camera; the camera eases towards it
+#[derive(Resource, Default)]
+pub struct SpectatorCamera {
+    /// Client ID of the player being followed, or `None` for the free camera
+    following: Option<u64>,
+    position: Vec2,
+}
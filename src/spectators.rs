@@ -0,0 +1,88 @@
+use crate::{
+    BoxCollectable, ServerMessage,
+    admin::Kicks,
+    map::MapDefinition,
+    powerups::ActiveEffects,
+    protocol::{CollectibleKind, ConnectData, PlayerProfile},
+    server::{
+        CollectibleId, Player, Score, ServerNetwork, collectible_snapshot, handle_client_connects,
+        send_player_status,
+    },
+};
+use bevy::{platform::collections::HashSet, prelude::*};
+use bevy_renet2::{netcode::NetcodeServerTransport, prelude::ServerEvent};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Spectators>().add_systems(
+        Update,
+        // Runs after connection handling so clients it refused can be left alone
+        handle_spectator_connects.after(handle_client_connects),
+    );
+}
+
+// === Spectators ===
+/// Spectators get the same world as players but no player of their own, so they take no
+/// part in scoring, collisions or team balance
+#[allow(
+    clippy::too_many_arguments,
+    reason = "sends a new spectator the whole world"
+)]
+fn handle_spectator_connects(
+    mut events: EventReader<ServerEvent>,
+    transport: Res<NetcodeServerTransport>,
+    map: Res<MapDefinition>,
+    kicks: Res<Kicks>,
+    mut spectators: ResMut<Spectators>,
+    mut network: ServerNetwork,
+    boxes: Query<(&CollectibleId, &CollectibleKind, &Transform), With<BoxCollectable>>,
+    players: Query<(&Player, &PlayerProfile, &Score, &ActiveEffects)>,
+) {
+    for event in events.read() {
+        match event {
+            ServerEvent::ClientConnected { client_id } => {
+                let spectating = transport
+                    .user_data(*client_id)
+                    .and_then(|data| ConnectData::from_user_data(&data))
+                    .is_some_and(|data| data.spectate);
+                if !spectating || kicks.contains(*client_id) {
+                    continue;
+                }
+
+                spectators.0.insert(*client_id);
+                info!("Client {client_id} joined as a spectator");
+
+                network.send(*client_id, &ServerMessage::MapData(map.clone()));
+                network.send(
+                    *client_id,
+                    &ServerMessage::SpawnCollectibles(collectible_snapshot(&boxes)),
+                );
+                for (player, profile, score, effects) in players.iter() {
+                    network.send(
+                        *client_id,
+                        &ServerMessage::SpawnRemotePlayer {
+                            client_id: player.client_id,
+                            profile: profile.clone(),
+                        },
+                    );
+                    send_player_status(
+                        &mut network,
+                        *client_id,
+                        player.client_id,
+                        Some(score),
+                        Some(effects),
+                    );
+                }
+            }
+            ServerEvent::ClientDisconnected { client_id, .. } => {
+                if spectators.0.remove(client_id) {
+                    info!("Spectator {client_id} left");
+                }
+            }
+        }
+    }
+}
+
+// === Components and Resources ===
+/// Connected clients watching without a player
+#[derive(Resource, Default)]
+pub struct Spectators(pub HashSet<u64>);
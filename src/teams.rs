@@ -0,0 +1,129 @@
+use crate::{
+    ServerMessage,
+    protocol::{PlayerProfile, Team},
+    rounds::{GameMode, MatchRules},
+    server::ServerNetwork,
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_renet2::prelude::ServerEvent;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TeamScores>()
+        .add_systems(Update, send_teams_on_connect.run_if(team_mode));
+}
+
+fn team_mode(rules: Res<MatchRules>) -> bool {
+    rules.mode == GameMode::Teams
+}
+
+/// The requested team if joining it keeps the teams even, otherwise whichever is smaller
+pub fn assign_team<'a>(
+    requested: Option<Team>,
+    taken: impl Iterator<Item = &'a PlayerProfile>,
+) -> Team {
+    let mut counts: HashMap<Team, usize> = HashMap::default();
+    for team in taken.filter_map(|profile| profile.team) {
+        *counts.entry(team).or_default() += 1;
+    }
+    let count = |team: Team| counts.get(&team).copied().unwrap_or(0);
+
+    let smallest = Team::ALL
+        .into_iter()
+        .min_by_key(|team| count(*team))
+        .unwrap_or(Team::Red);
+    match requested {
+        Some(team) if count(team) <= count(smallest) => team,
+        _ => smallest,
+    }
+}
+
+// === Teams ===
+fn send_teams_on_connect(
+    mut events: EventReader<ServerEvent>,
+    team_scores: Res<TeamScores>,
+    mut network: ServerNetwork,
+) {
+    for event in events.read() {
+        let ServerEvent::ClientConnected { client_id } = event else {
+            continue;
+        };
+
+        for team in Team::ALL {
+            network.send(
+                *client_id,
+                &ServerMessage::TeamScore {
+                    team,
+                    score: team_scores.get(team),
+                },
+            );
+        }
+    }
+}
+
+// === Components and Resources ===
+/// Points each team has delivered this round
+#[derive(Resource, Default)]
+pub struct TeamScores(HashMap<Team, u32>);
+
+impl TeamScores {
+    pub fn get(&self, team: Team) -> u32 {
+        self.0.get(&team).copied().unwrap_or(0)
+    }
+
+    /// Adds to a team's score and returns the new total
+    pub fn add(&mut self, team: Team, points: u32) -> u32 {
+        let score = self.0.entry(team).or_default();
+        *score += points;
+        *score
+    }
+
+    pub fn reset(&mut self) {
+        self.0.clear();
+    }
+
+    /// The team ahead, or `None` on a tie
+    pub fn leader(&self) -> Option<Team> {
+        match (self.get(Team::Red), self.get(Team::Blue)) {
+            (red, blue) if red > blue => Some(Team::Red),
+            (red, blue) if blue > red => Some(Team::Blue),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn on(teams: &[Option<Team>]) -> Vec<PlayerProfile> {
+        teams
+            .iter()
+            .map(|team| PlayerProfile {
+                team: *team,
+                ..default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn first_player_gets_what_they_ask_for() {
+        assert_eq!(assign_team(Some(Team::Blue), [].iter()), Team::Blue);
+        assert_eq!(assign_team(None, [].iter()), Team::Red);
+    }
+
+    #[test]
+    fn requests_that_unbalance_the_teams_are_overridden() {
+        let players = on(&[Some(Team::Red)]);
+        assert_eq!(assign_team(Some(Team::Red), players.iter()), Team::Blue);
+        assert_eq!(assign_team(Some(Team::Blue), players.iter()), Team::Blue);
+    }
+
+    #[test]
+    fn unrequested_players_fill_the_smaller_team() {
+        let players = on(&[Some(Team::Red), Some(Team::Red), Some(Team::Blue), None]);
+        assert_eq!(assign_team(None, players.iter()), Team::Blue);
+
+        let even = on(&[Some(Team::Red), Some(Team::Blue)]);
+        assert_eq!(assign_team(Some(Team::Blue), even.iter()), Team::Blue);
+    }
+}